@@ -1,27 +1,25 @@
 use anyhow::Result;
 use crossterm::event;
-use futures::StreamExt;
-use ratatui::{style::Stylize, text::*, widgets::*, Terminal};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::*,
+    widgets::*,
+};
 use rizzup::prelude::*;
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
-enum Message {
-    Tick,
+#[derive(Debug, Clone, Copy)]
+enum Blink {
+    Pause,
+    Resume,
 }
 
 fn input() -> RatView {
     let input = create_signal("".to_string());
     let blink = create_signal(true);
-    let active = create_signal(true);
-
-    let ticker = create_async_task(active.clone(), move |active, send| async move {
-        if !active {
-            return;
-        }
-        loop {
-            send.send(Message::Tick);
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
+    let active = use_interval(Duration::from_millis(500), move || {
+        blink.update(|v| *v = !*v)
     });
 
     let input_c = input.clone();
@@ -31,28 +29,35 @@ fn input() -> RatView {
         blink.set(true);
     });
 
+    use_keymap([
+        ("up", Blink::Pause, "Pause blinking"),
+        ("down", Blink::Resume, "Resume blinking"),
+    ])
+    .named("Input");
+
+    on(move |ev: &Blink| match ev {
+        Blink::Pause => active.set(false),
+        Blink::Resume => active.set(true),
+    });
+
     let input_c = input.clone();
-    on(move |ev: &event::KeyCode| match ev {
-        event::KeyCode::Char(ch) => input_c.update(|x| x.push(*ch)),
+    on(move |ev: &event::KeyEvent| match ev.code {
+        event::KeyCode::Char(ch) => input_c.update(|x| x.push(ch)),
         event::KeyCode::Backspace => input_c.update(|x| {
             x.pop();
         }),
-        event::KeyCode::Up => active.set(false),
-        event::KeyCode::Down => active.set(true),
         _ => {}
     });
 
-    on(move |ev: &Message| match ev {
-        Message::Tick => blink.update(|v| *v = !*v),
-    });
-
     widget_ref(move || {
         let block = Block::default()
             .padding(Padding::horizontal(1))
             .borders(Borders::all())
             .title("Start typeing")
-            .title("(Press esc to exit)")
-            .title(format!("{:?}", ticker.get_state()));
+            .title(match active.get() {
+                true => "Blinking",
+                false => "Paused",
+            });
 
         let lines = Line::from(vec![
             Span::from(format!("{}", input.get())),
@@ -66,60 +71,26 @@ fn input() -> RatView {
     })
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut term = init_tui()?;
-    init_panic_hook();
-
-    create_async_scope(move |handle| async move {
-        let mut reader = crossterm::event::EventStream::new();
-        let app = input();
-        loop {
-            term.draw(|f| f.render_widget_ref(app, f.size()))?;
+fn app() -> RatView {
+    use_keymap([("esc", Quit, "Exit")]).named("App");
 
-            tokio::select! {
-                _ = handle.listen() => {},
-                ev = reader.next() => match ev {
-                    Some(Ok(event)) => {
-                        if let event::Event::Key(key) = event {
-                            if key.kind == event::KeyEventKind::Press {
-                                send(key.code)
-                            }
-                            if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Esc {
-                                handle.shutdown().await;
-                                break;
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
+    let input = input();
+    let help = help_overlay();
 
-        restore_tui()?;
+    render(move |area, buf| {
+        let areas: [Rect; 2] = Layout::new(
+            ratatui::layout::Direction::Vertical,
+            [Constraint::Max(3), Constraint::Fill(1)],
+        )
+        .areas(area);
 
-        Ok::<(), anyhow::Error>(())
-    }).await;
-
-    Ok(())
-}
-
-pub fn init_panic_hook() {
-    let original_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |panic_info| {
-        let _ = restore_tui();
-        original_hook(panic_info);
-    }));
-}
-
-pub fn init_tui() -> std::io::Result<Terminal<impl ratatui::backend::Backend>> {
-    crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(std::io::stderr(), crossterm::terminal::EnterAlternateScreen)?;
-    Terminal::new(ratatui::backend::CrosstermBackend::new(std::io::stderr()))
+        input.render_ref(areas[0], buf);
+        help.render_ref(areas[1], buf);
+    })
 }
 
-pub fn restore_tui() -> Result<()> {
-    crossterm::terminal::disable_raw_mode()?;
-    crossterm::execute!(std::io::stderr(), crossterm::terminal::LeaveAlternateScreen)?;
+#[tokio::main]
+async fn main() -> Result<()> {
+    App::new(app).run_async().await?;
     Ok(())
 }
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use ratatui::{
+    style::{Color, Style},
+    widgets::*,
+};
+use rizzup::prelude::*;
+
+const FILES: [&str; 6] = [
+    "Cargo.toml",
+    "src/lib.rs",
+    "src/app.rs",
+    "src/tasks.rs",
+    "src/signal.rs",
+    "README.md",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Downloaded(usize);
+
+fn progress() -> RatView {
+    use_keymap([("esc", Quit)]);
+    let done = create_signal(0);
+
+    create_async_task(create_signal(()), |_, send| async move {
+        for i in 0..FILES.len() {
+            tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
+            send.send(Downloaded(i));
+        }
+    });
+
+    on(move |Downloaded(i): &Downloaded| {
+        println_above(format!("Downloaded {}", FILES[*i]));
+        done.set(i + 1);
+        if i + 1 == FILES.len() {
+            send(Quit);
+        }
+    });
+
+    widget_ref(move || {
+        Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::all())
+                    .title("Downloading"),
+            )
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(done.get() as f64 / FILES.len() as f64)
+            .label(format!("{}/{}", done.get(), FILES.len()))
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    App::new(progress).inline(3).run_async().await?;
+    Ok(())
+}
@@ -4,9 +4,8 @@ use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     widgets::*,
-    Terminal,
 };
-use rizzup::{prelude::*, ratatui::widget_ref};
+use rizzup::{nodes::IntoScope, prelude::*, ratatui::widget_ref};
 
 #[derive(Default, Clone)]
 struct Todo {
@@ -23,33 +22,31 @@ impl Todo {
     }
 }
 
-fn todo_text_input(focused: ReadSignal<bool>) -> RatView {
+fn todo_text_input() -> RatView {
+    let focus = use_focus();
+    let focus_scope = use_context::<FocusScope>();
     let value = create_signal("".to_string());
     let todos = use_context::<Signal<Vec<Todo>>>();
 
     let value_c = value.clone();
-    on(move |key: &event::KeyCode| {
-        if focused.get() == false {
-            return;
-        }
-        match key {
-            event::KeyCode::Char(ch) => value_c.update(|x| x.push(*ch)),
-            event::KeyCode::Backspace => value_c.update(|x| {
-                x.pop();
-            }),
-            event::KeyCode::Enter => {
-                todos.update(|t| t.push(Todo::new(value_c.get())));
-                value_c.set("".into());
-            }
-            _ => {}
+    on(move |key: &event::KeyEvent| match key.code {
+        event::KeyCode::Char(ch) => value_c.update(|x| x.push(ch)),
+        event::KeyCode::Backspace => value_c.update(|x| {
+            x.pop();
+        }),
+        event::KeyCode::Enter => {
+            todos.update(|t| t.push(Todo::new(value_c.get())));
+            value_c.set("".into());
         }
+        event::KeyCode::Down => focus_scope.focus_next(),
+        _ => {}
     });
 
     widget_ref(move || {
         let block = Block::default()
             .padding(Padding::horizontal(1))
             .borders(Borders::all())
-            .border_style(match focused.get() {
+            .border_style(match focus.is_focused() {
                 true => Color::Cyan,
                 false => Color::default(),
             })
@@ -58,7 +55,8 @@ fn todo_text_input(focused: ReadSignal<bool>) -> RatView {
     })
 }
 
-fn todo_list(focused: ReadSignal<bool>) -> RatView {
+fn todo_list() -> RatView {
+    let focus = use_focus();
     let todos = use_context::<Signal<Vec<Todo>>>();
     let state = create_signal({
         let mut s = ListState::default();
@@ -68,12 +66,9 @@ fn todo_list(focused: ReadSignal<bool>) -> RatView {
 
     let todos_c = todos.clone();
     let state_c = state.clone();
-    on(move |key: &event::KeyCode| {
-        if focused.get() == false {
-            return;
-        }
+    on(move |key: &event::KeyEvent| {
         let size = todos_c.get().len();
-        match key {
+        match key.code {
             event::KeyCode::Enter => todos_c.update(|t| {
                 if let Some(s) = state_c.get().selected() {
                     if let Some(todo) = t.get_mut(s) {
@@ -107,11 +102,26 @@ fn todo_list(focused: ReadSignal<bool>) -> RatView {
         }
     });
 
+    let todos_c = todos.clone();
+    let state_c = state.clone();
+    on_mouse(move |m: &Mouse| {
+        let event::MouseEventKind::Down(_) = m.event.kind else {
+            return;
+        };
+        // Rows start below the top border
+        let (_, row) = m.position();
+        let index = state_c.get().offset() + row.saturating_sub(1) as usize;
+        if row > 0 && index < todos_c.get().len() {
+            state_c.update(|s| s.select(Some(index)));
+        }
+        focus.focus();
+    });
+
     statefull_widget_ref(state, move || {
         let block = Block::default()
             .padding(Padding::horizontal(1))
             .borders(Borders::all())
-            .border_style(match focused.get() {
+            .border_style(match focus.is_focused() {
                 true => Color::Cyan,
                 false => Color::default(),
             })
@@ -131,30 +141,23 @@ fn todo_list(focused: ReadSignal<bool>) -> RatView {
     })
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum Focus {
-    Input,
-    List,
-}
-
 fn todo_list_app() -> RatView {
-    let focus = create_signal(Focus::Input);
+    use_keymap([("esc", Quit)]);
     provide_context(create_signal::<Vec<Todo>>(vec![]));
+    create_focus_scope();
 
-    let todo_list = todo_list(create_memo(move || focus.get() == Focus::List));
-    let todo_list_input = todo_text_input(create_memo(move || focus.get() == Focus::Input));
+    let todo_list_input = component(todo_text_input);
+    let todo_list = component(todo_list);
 
-    on(move |ev: &event::KeyCode| match ev {
-        event::KeyCode::Char(_) => focus.update(|v| *v = Focus::Input),
-        event::KeyCode::Down => focus.update(|v| match v {
-            Focus::Input => *v = Focus::List,
-            Focus::List => {}
-        }),
-        event::KeyCode::Tab => focus.update(|v| match v {
-            Focus::Input => *v = Focus::List,
-            Focus::List => *v = Focus::Input,
-        }),
-        _ => {}
+    // Typing while the list is focused moves focus to the input and types there
+    on(move |key: &event::KeyEvent| {
+        let typing = matches!(key.code, event::KeyCode::Char(_));
+        if !typing || focused_scope() == Some(todo_list_input.into_scope()) {
+            return Propagation::Continue;
+        }
+        focus(todo_list_input);
+        todo_list_input.send(*key);
+        Propagation::Stop
     });
 
     render(move |area, buf| {
@@ -170,50 +173,6 @@ fn todo_list_app() -> RatView {
 }
 
 fn main() -> Result<()> {
-    let mut term = init_tui()?;
-    init_panic_hook();
-
-    with_tracking_scope(|| {
-        let app = todo_list_app();
-
-        loop {
-            term.draw(|f| f.render_widget_ref(app, f.size()))?;
-
-            let event = event::read()?;
-            if let event::Event::Key(key) = event {
-                if key.kind == event::KeyEventKind::Press {
-                    send(key.code)
-                }
-                if key.kind == event::KeyEventKind::Press && key.code == event::KeyCode::Esc {
-                    break;
-                }
-            }
-        }
-
-        restore_tui()?;
-
-        Ok::<(), anyhow::Error>(())
-    })?;
-
-    Ok(())
-}
-
-pub fn init_panic_hook() {
-    let original_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |panic_info| {
-        let _ = restore_tui();
-        original_hook(panic_info);
-    }));
-}
-
-pub fn init_tui() -> std::io::Result<Terminal<impl ratatui::backend::Backend>> {
-    crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(std::io::stderr(), crossterm::terminal::EnterAlternateScreen)?;
-    Terminal::new(ratatui::backend::CrosstermBackend::new(std::io::stderr()))
-}
-
-pub fn restore_tui() -> Result<()> {
-    crossterm::terminal::disable_raw_mode()?;
-    crossterm::execute!(std::io::stderr(), crossterm::terminal::LeaveAlternateScreen)?;
+    App::new(todo_list_app).enable_mouse().run()?;
     Ok(())
 }
@@ -0,0 +1,209 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    pin::Pin,
+    rc::Rc,
+};
+
+use futures::Future;
+
+use crate::{environment::*, nodes::Scope, signal::*, tasks::TaskRunner};
+
+type Run<I, O, E> = Box<dyn Fn(I) -> Pin<Box<dyn Future<Output = Result<O, E>> + Send>>>;
+type Optimistic<I> = Box<dyn Fn(&I) -> Box<dyn FnOnce()>>;
+
+/// Result of a dispatch sent back to the scope that created the action
+struct Completed<O, E> {
+    action: Scope,
+    dispatch: u64,
+    result: Result<O, E>,
+}
+
+struct ActionState<I, O, E> {
+    run: Run<I, O, E>,
+    optimistic: RefCell<Option<Optimistic<I>>>,
+    /// Undo of the optimistic update of each dispatch still running
+    rollbacks: RefCell<HashMap<u64, Box<dyn FnOnce()>>>,
+    dispatched: Cell<u64>,
+    tasks: TaskRunner,
+    owner: Scope,
+    /// Tells the dispatches and results of this action apart from other actions of the owner
+    id: Scope,
+}
+
+/// Async operation run on demand, see [`create_action`]
+pub struct Action<I, O, E> {
+    state: Rc<ActionState<I, O, E>>,
+    running: Signal<usize>,
+    last_result: Signal<Option<Result<O, E>>>,
+    version: Signal<usize>,
+}
+
+impl<I, O, E> Clone for Action<I, O, E> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            running: self.running,
+            last_result: self.last_result.clone(),
+            version: self.version,
+        }
+    }
+}
+
+impl<I: 'static, O: Clone + Send + 'static, E: Clone + Send + 'static> Action<I, O, E> {
+    /// Applies update to target when the action is dispatched and undoes it with rollback if
+    /// that dispatch fails. Rollback only undoes the change of its own dispatch, so changes
+    /// made to target since are kept
+    pub fn optimistic<T: 'static>(
+        self,
+        target: Signal<T>,
+        update: impl Fn(&mut T, &I) + 'static,
+        rollback: impl Fn(&mut T, &I) + 'static,
+    ) -> Self
+    where
+        I: Clone,
+    {
+        let rollback = Rc::new(rollback);
+        let apply = move |input: &I| -> Box<dyn FnOnce()> {
+            let target = target.clone();
+            target.update(|v| update(v, input));
+            let (rollback, input) = (rollback.clone(), input.clone());
+            Box::new(move || target.update(|v| rollback(v, &input)))
+        };
+        self.state.optimistic.replace(Some(Box::new(apply)));
+        self
+    }
+
+    /// Runs the action with input on the [`TaskRunner`], the result is delivered to the scope
+    /// that created the action
+    pub fn dispatch(&self, input: I) {
+        let state = &self.state;
+        let dispatch = state.dispatched.get() + 1;
+        state.dispatched.set(dispatch);
+        if let Some(apply) = &*state.optimistic.borrow() {
+            let rollback = apply(&input);
+            state.rollbacks.borrow_mut().insert(dispatch, rollback);
+        }
+        self.running.update(|r| *r += 1);
+
+        let action = state.id;
+        let tx = state.tasks.transmitter(state.owner);
+        let fut = (state.run)(input);
+        state.tasks.spawn_cancellable(action, async move {
+            let result = fut.await;
+            tx.send(Completed {
+                action,
+                dispatch,
+                result,
+            });
+        });
+    }
+
+    /// Whether a dispatch is still running
+    pub fn pending(&self) -> bool {
+        self.running.get() > 0
+    }
+
+    /// Result of the dispatch that completed last
+    pub fn last_result(&self) -> Option<Result<O, E>> {
+        self.last_result.get()
+    }
+
+    /// Number of dispatches that completed
+    pub fn version(&self) -> usize {
+        self.version.get()
+    }
+}
+
+/// Creates an action that runs f with the input of each [`Action::dispatch`]. Dispatches run
+/// concurrently and are cancelled when the current scope is disposed
+pub fn create_action<I, O, E, Fu>(f: impl Fn(I) -> Fu + 'static) -> Action<I, O, E>
+where
+    I: 'static,
+    O: Clone + Send + 'static,
+    E: Clone + Send + 'static,
+    Fu: Future<Output = Result<O, E>> + Send + 'static,
+{
+    let tasks = use_context::<TaskRunner>();
+    let action = Action {
+        state: Rc::new(ActionState {
+            run: Box::new(move |input| Box::pin(f(input))),
+            optimistic: RefCell::new(None),
+            rollbacks: RefCell::default(),
+            dispatched: Cell::new(0),
+            tasks: tasks.clone(),
+            owner: with_runtime(|r| r.get_current_scope()),
+            id: create_id(),
+        }),
+        running: create_signal(0),
+        last_result: create_signal(None),
+        version: create_signal(0),
+    };
+    let id = action.state.id;
+    on_cleanup(move || tasks.cancel(id));
+
+    let action_c = action.clone();
+    on(move |done: &Completed<O, E>| {
+        if done.action != id {
+            return;
+        }
+        let rollback = action_c.state.rollbacks.borrow_mut().remove(&done.dispatch);
+        if let (Some(rollback), Err(_)) = (rollback, &done.result) {
+            rollback();
+        }
+        action_c.running.update(|r| *r -= 1);
+        action_c.last_result.set(Some(done.result.clone()));
+        action_c.version.update(|v| *v += 1);
+    });
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ratatui::widget_ref, testing::TestApp};
+    use std::time::Duration;
+
+    #[test]
+    fn test_action_optimistic_rollback() {
+        let (mut app, (items, save)) = TestApp::mount(20, 1, || {
+            let items = create_signal(vec![]);
+            let save = create_action(|item: &'static str| async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                match item {
+                    "bad" => Err("rejected"),
+                    item => Ok(item.len()),
+                }
+            })
+            .optimistic(
+                items.clone(),
+                |items, item| items.push(*item),
+                |items, item| items.retain(|i| i != item),
+            );
+            let values = (items.clone(), save.clone());
+            (
+                widget_ref(move || format!("{} {}", items.get().join(","), save.pending())),
+                values,
+            )
+        });
+        assert_eq!(app.to_string(), " false\n");
+
+        app.enter(|| save.dispatch("a"));
+        app.advance(Duration::from_millis(50));
+        assert_eq!(app.to_string(), "a true\n");
+        app.enter(|| save.dispatch("bad"));
+        app.advance(Duration::from_millis(25));
+        app.enter(|| save.dispatch("b"));
+        app.advance(Duration::from_millis(25));
+        assert_eq!(app.to_string(), "a,bad,b true\n");
+        assert_eq!(app.enter(|| save.last_result()), Some(Ok(1)));
+
+        app.advance(Duration::from_millis(50));
+        assert_eq!(app.to_string(), "a,b true\n", "Later dispatch kept");
+        assert_eq!(app.enter(|| save.last_result()), Some(Err("rejected")));
+
+        app.advance(Duration::from_millis(50));
+        assert_eq!(app.enter(|| items.get()), vec!["a", "b"]);
+        assert_eq!(app.enter(|| save.version()), 3);
+    }
+}
@@ -0,0 +1,531 @@
+use std::{
+    cell::RefCell,
+    io::{Stderr, Write},
+    marker::PhantomData,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{Event, EventStream};
+use futures::StreamExt;
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    text::Line,
+    widgets::{Paragraph, Widget},
+    Terminal, TerminalOptions, Viewport,
+};
+
+use crate::{
+    environment::*,
+    events::send_event,
+    mouse::begin_frame,
+    ratatui::RatView,
+    record::{Recorded, Recorder},
+    scheduler::FrameLimiter,
+    signal::*,
+    tasks::*,
+};
+
+/// Default message that exits an [`App`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quit;
+
+#[derive(Debug, Clone, Copy)]
+struct Options {
+    mouse: bool,
+    /// Height of the inline viewport, fullscreen when None
+    inline: Option<u16>,
+}
+
+/// Runs a root view in the terminal, the app exits when a Q message is sent or emitted from
+/// the root or any scope below it. The message is the result of the app, returned by
+/// [`App::run`] and [`App::run_async`]
+pub struct App<Q = Quit> {
+    root: Box<dyn FnOnce() -> RatView>,
+    options: Options,
+    fps: u32,
+    record: Option<PathBuf>,
+    quit: PhantomData<Q>,
+}
+
+impl App<Quit> {
+    pub fn new(root: impl FnOnce() -> RatView + 'static) -> Self {
+        Self {
+            root: Box::new(root),
+            options: Options {
+                mouse: false,
+                inline: None,
+            },
+            fps: 60,
+            record: None,
+            quit: PhantomData,
+        }
+    }
+}
+
+impl<Q: Clone + 'static> App<Q> {
+    /// Exit when a message of type M is sent anywhere in the app, use a message carrying the
+    /// value the root should return
+    pub fn quit_on<M: Clone + 'static>(self) -> App<M> {
+        App {
+            root: self.root,
+            options: self.options,
+            fps: self.fps,
+            record: self.record,
+            quit: PhantomData,
+        }
+    }
+
+    /// Capture mouse events so they are delivered to the views with [`crate::mouse::on_mouse`]
+    pub fn enable_mouse(mut self) -> Self {
+        self.options.mouse = true;
+        self
+    }
+
+    /// Draw in a region of height lines below the shell output instead of the alternate
+    /// screen, see [`println_above`] and [`use_viewport_height`]
+    pub fn inline(mut self, height: u16) -> Self {
+        self.options.inline = Some(height);
+        self
+    }
+
+    /// Limits how often the app is drawn, 0 draws on every change. Defaults to 60
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Records the terminal events and task messages of the session to a file that can be
+    /// replayed with `testing::TestApp::replay` from the `testing` feature. Also enabled by
+    /// setting `RIZZUP_RECORD` to a path
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+
+    fn record_path(&self) -> Option<PathBuf> {
+        let env = || std::env::var_os("RIZZUP_RECORD").map(PathBuf::from);
+        self.record.clone().or_else(env)
+    }
+
+    /// Runs until the quit message is sent and returns it
+    pub fn run(self) -> anyhow::Result<Q> {
+        let mut screen = Screen::new(self.options, self.fps, self.record_path().as_deref())?;
+        let result = with_tracking_scope(|| {
+            let (quit, inline, root) = self.mount();
+            screen.inline = inline;
+            loop {
+                if let Some(message) = quit.take() {
+                    return Ok(message);
+                }
+                if let Some(wait) = screen.draw(root)? {
+                    if !crossterm::event::poll(wait)? {
+                        continue;
+                    }
+                }
+                // Handle every event that is already waiting before drawing again
+                loop {
+                    screen.event(crossterm::event::read()?);
+                    if quit.borrow().is_some() || !crossterm::event::poll(Duration::ZERO)? {
+                        break;
+                    }
+                }
+            }
+        });
+        screen.restore()?;
+        result
+    }
+
+    /// Runs the app with a [`TaskRunner`] in context, task messages are delivered between
+    /// terminal events
+    pub async fn run_async(self) -> anyhow::Result<Q> {
+        let mut screen = Screen::new(self.options, self.fps, self.record_path().as_deref())?;
+        let (result, screen) = create_async_scope(move |tasks| async move {
+            let (quit, inline, root) = self.mount();
+            screen.inline = inline;
+            let mut events = EventStream::new();
+            let result = loop {
+                if let Some(message) = quit.take() {
+                    break Ok(message);
+                }
+                let wait = match screen.draw(root) {
+                    Ok(wait) => wait,
+                    Err(e) => break Err(e.into()),
+                };
+                tokio::select! {
+                    _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {},
+                    message = tasks.recv() => {
+                        let mut message = message;
+                        while let Some(m) = message {
+                            screen.task_message(m);
+                            message = tasks.try_recv();
+                        }
+                    }
+                    event = events.next() => match event {
+                        Some(Ok(event)) => screen.event(event),
+                        Some(Err(e)) => break Err(e.into()),
+                        None => break Err(anyhow::anyhow!("Terminal event stream closed")),
+                    }
+                }
+            };
+            tasks.shutdown().await;
+            (result, screen)
+        })
+        .await;
+        screen.restore()?;
+        result
+    }
+
+    /// Watches for the quit message under the current scope, provides the inline viewport
+    /// state and creates the root view
+    fn mount(self) -> (Rc<RefCell<Option<Q>>>, Option<Inline>, RatView) {
+        let quit = Rc::new(RefCell::new(None));
+        let quit_c = quit.clone();
+        watch(move |message: &Q| {
+            quit_c.replace(Some(message.clone()));
+        });
+        let inline = if let Some(height) = self.options.inline {
+            let height = create_signal(height);
+            create_memo(move || {
+                height.get();
+                request_redraw();
+            });
+            let inline = Inline {
+                height,
+                above: Rc::default(),
+            };
+            provide_context(inline.clone());
+            Some(inline)
+        } else {
+            None
+        };
+        (quit, inline, (self.root)())
+    }
+}
+
+/// State of an inline viewport provided as context by the app
+#[derive(Clone)]
+struct Inline {
+    height: Signal<u16>,
+    /// Lines waiting to be printed above the viewport
+    above: Rc<RefCell<Vec<Line<'static>>>>,
+}
+
+/// Prints a line above the inline viewport of the running [`App`], does nothing in a
+/// fullscreen app
+pub fn println_above(line: impl Into<Line<'static>>) {
+    if let Some(inline) = use_context_rc_option::<Inline>() {
+        inline.above.borrow_mut().push(line.into());
+        request_redraw();
+    }
+}
+
+/// Height of the inline viewport of the running [`App`], setting it resizes the viewport.
+/// None in a fullscreen app
+pub fn use_viewport_height() -> Option<Signal<u16>> {
+    use_context_rc_option::<Inline>().map(|inline| inline.height)
+}
+
+/// Owns the terminal and draws the root when a redraw was requested and the frame limit
+/// allows it
+struct Screen<B: Backend = CrosstermBackend<Stderr>> {
+    terminal: Terminal<B>,
+    /// Creates the backend of a new terminal when the inline viewport is resized
+    backend: Box<dyn Fn() -> B>,
+    options: Options,
+    inline: Option<Inline>,
+    limiter: FrameLimiter,
+    pending: bool,
+    recorder: Option<Recorder>,
+    /// Restores the real terminal, None for a test backend
+    guard: Option<TerminalGuard>,
+}
+
+impl Screen {
+    fn new(options: Options, fps: u32, record: Option<&Path>) -> std::io::Result<Self> {
+        let guard = TerminalGuard::init(options)?;
+        let mut screen =
+            Self::with_backend(options, fps, || CrosstermBackend::new(std::io::stderr()))?;
+        screen.guard = Some(guard);
+        if let Some(path) = record {
+            let area = screen.terminal.get_frame().size();
+            screen.recorder = Some(Recorder::create(path, (area.width, area.height))?);
+        }
+        Ok(screen)
+    }
+
+    fn restore(mut self) -> std::io::Result<()> {
+        if self.options.inline.is_some() {
+            // Leave the last frame on screen and continue below it
+            let area = self.terminal.get_frame().size();
+            self.terminal
+                .set_cursor(0, area.bottom().saturating_sub(1))?;
+        }
+        if let Some(guard) = self.guard.take() {
+            guard.restore()?;
+        }
+        if self.options.inline.is_some() {
+            writeln!(std::io::stderr())?;
+        }
+        Ok(())
+    }
+}
+
+impl<B: Backend> Screen<B> {
+    fn with_backend(
+        options: Options,
+        fps: u32,
+        backend: impl Fn() -> B + 'static,
+    ) -> std::io::Result<Self> {
+        let terminal = new_terminal(options, backend())?;
+        let size = terminal.size()?;
+        with_runtime(|r| r.terminal_size.set(size));
+        Ok(Self {
+            terminal,
+            backend: Box::new(backend),
+            options,
+            inline: None,
+            limiter: FrameLimiter::new(fps),
+            pending: true,
+            recorder: None,
+            guard: None,
+        })
+    }
+
+    fn event(&mut self, event: Event) {
+        self.pending |= matches!(event, Event::Resize(..));
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&Recorded::Event(event.clone()));
+        }
+        send_event(event);
+    }
+
+    fn task_message(&mut self, message: Message) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&Recorded::Task(message.1.to_string()));
+        }
+        TaskRunner::deliver(message);
+    }
+
+    /// Returns how long to wait before drawing a pending redraw
+    fn draw(&mut self, root: RatView) -> std::io::Result<Option<Duration>> {
+        self.pending |= with_runtime(|r| r.scheduler.take_redraw());
+        if !self.pending {
+            return Ok(None);
+        }
+        let wait = self.limiter.remaining(Instant::now());
+        if !wait.is_zero() {
+            return Ok(Some(wait));
+        }
+        if let Some(inline) = self.inline.clone() {
+            self.update_inline(&inline)?;
+        }
+        begin_frame();
+        self.terminal
+            .draw(|f| f.render_widget_ref(root, f.size()))?;
+        with_runtime(|r| r.check_leaks());
+        self.limiter.frame(Instant::now());
+        self.pending = false;
+        Ok(None)
+    }
+
+    /// Resizes the viewport to the height signal and prints the lines waiting above it
+    fn update_inline(&mut self, inline: &Inline) -> std::io::Result<()> {
+        let height = inline.height.get_untracked();
+        if Some(height) != self.options.inline {
+            self.terminal.clear()?;
+            self.options.inline = Some(height);
+            self.terminal = new_terminal(self.options, (self.backend)())?;
+        }
+        let lines = std::mem::take(&mut *inline.above.borrow_mut());
+        if !lines.is_empty() {
+            let height = lines.len() as u16;
+            self.terminal.insert_before(height, |buf| {
+                Paragraph::new(lines).render(buf.area, buf);
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn new_terminal<B: Backend>(options: Options, backend: B) -> std::io::Result<Terminal<B>> {
+    let viewport = match options.inline {
+        Some(height) => Viewport::Inline(height),
+        None => Viewport::Fullscreen,
+    };
+    Terminal::with_options(backend, TerminalOptions { viewport })
+}
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Raw mode, alternate screen and panic hook of a running app, restored when dropped so the
+/// terminal is usable again after an error
+struct TerminalGuard {
+    options: Options,
+    /// Hook that was installed before the app's, put back on restore
+    previous: Option<Arc<Mutex<Option<PanicHook>>>>,
+}
+
+impl TerminalGuard {
+    fn init(options: Options) -> std::io::Result<Self> {
+        let previous = Arc::new(Mutex::new(Some(std::panic::take_hook())));
+        let previous_c = previous.clone();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            let _ = restore_terminal(options);
+            let hook = previous_c.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(hook) = &*hook {
+                hook(panic_info);
+            }
+        }));
+        let guard = Self {
+            options,
+            previous: Some(previous),
+        };
+        crossterm::terminal::enable_raw_mode()?;
+        if options.inline.is_none() {
+            crossterm::execute!(std::io::stderr(), crossterm::terminal::EnterAlternateScreen)?;
+        }
+        if options.mouse {
+            crossterm::execute!(std::io::stderr(), crossterm::event::EnableMouseCapture)?;
+        }
+        Ok(guard)
+    }
+
+    fn restore(mut self) -> std::io::Result<()> {
+        self.restore_hook();
+        restore_terminal(self.options)
+    }
+
+    /// Replaces the app's panic hook with the one it wrapped
+    fn restore_hook(&mut self) {
+        let Some(previous) = self.previous.take() else {
+            return;
+        };
+        let _ = std::panic::take_hook();
+        let hook = previous.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(hook) = hook {
+            std::panic::set_hook(hook);
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // The panic hook already restored the terminal, and hooks can't be changed while
+        // panicking
+        if self.previous.is_some() && !std::thread::panicking() {
+            self.restore_hook();
+            let _ = restore_terminal(self.options);
+        }
+    }
+}
+
+fn restore_terminal(options: Options) -> std::io::Result<()> {
+    if options.mouse {
+        crossterm::execute!(std::io::stderr(), crossterm::event::DisableMouseCapture)?;
+    }
+    crossterm::terminal::disable_raw_mode()?;
+    if options.inline.is_none() {
+        crossterm::execute!(std::io::stderr(), crossterm::terminal::LeaveAlternateScreen)?;
+    }
+    crossterm::execute!(std::io::stderr(), crossterm::cursor::Show)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keymap::use_keymap, ratatui::widget_ref};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_quit_message_ends_app() {
+        with_tracking_scope(|| {
+            let app = App::new(|| {
+                let count = create_signal(0);
+                use_keymap([("q", Quit)]);
+                use_keymap([("enter", ())]);
+                on(move |_: &()| count.update(|c| *c += 1));
+                widget_ref(move || count.get().to_string())
+            });
+            let (quit, inline, _) = app.mount();
+            assert!(inline.is_none());
+            with_runtime(|r| r.scheduler.take_redraw());
+
+            let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+            send_event(key(KeyCode::Enter));
+            assert!(with_runtime(|r| r.scheduler.take_redraw()));
+            assert!(!with_runtime(|r| r.scheduler.take_redraw()));
+            assert_eq!(quit.take(), None);
+
+            send_event(key(KeyCode::Char('q')));
+            assert_eq!(quit.take(), Some(Quit));
+        })
+    }
+
+    #[test]
+    fn test_quit_sent_from_child_scope() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Choice(usize);
+
+        with_tracking_scope(|| {
+            let app = App::new(|| {
+                create_memo(|| use_keymap([("q", Quit)]));
+                widget_ref(|| "")
+            });
+            let (quit, _, _) = app.mount();
+            let key = Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE));
+            send_event(key);
+            assert_eq!(quit.take(), Some(Quit));
+
+            let app = App::new(|| {
+                create_memo(|| on(|n: &usize| send(Choice(*n))));
+                widget_ref(|| "")
+            });
+            let (quit, _, _) = app.quit_on::<Choice>().mount();
+            send(2_usize);
+            assert_eq!(quit.take(), Some(Choice(2)));
+        })
+    }
+
+    #[test]
+    fn test_inline_viewport_state() {
+        with_tracking_scope(|| {
+            let height = create_signal(None);
+            let app = App::new(move || {
+                height.set(use_viewport_height());
+                on(|line: &&'static str| println_above(*line));
+                widget_ref(|| "")
+            });
+            let (_, inline, _) = app.inline(3).mount();
+            let inline = inline.unwrap();
+            assert_eq!(height.get().map(|h| h.get()), Some(3));
+
+            with_runtime(|r| r.scheduler.take_redraw());
+            send("done");
+            assert_eq!(inline.above.borrow().clone(), vec![Line::from("done")]);
+            assert!(with_runtime(|r| r.scheduler.take_redraw()));
+        })
+    }
+
+    #[test]
+    fn test_inline_height_resizes_viewport() {
+        with_tracking_scope(|| {
+            let app = App::new(|| widget_ref(|| "")).inline(3);
+            let backend = || TestBackend::new(20, 10);
+            let mut screen = Screen::with_backend(app.options, 0, backend).unwrap();
+            let (_, inline, root) = app.mount();
+            let height = inline.as_ref().unwrap().height;
+            screen.inline = inline;
+            screen.draw(root).unwrap();
+            assert_eq!(screen.terminal.get_frame().size().height, 3);
+
+            height.set(5);
+            screen.draw(root).unwrap();
+            assert_eq!(screen.terminal.get_frame().size().height, 5);
+        })
+    }
+}
@@ -2,27 +2,105 @@ use std::{
     any::{Any, TypeId},
     cell::RefCell,
     collections::HashMap,
+    marker::PhantomData,
     rc::Rc,
 };
 
+use slotmap::SecondaryMap;
+
 use crate::nodes::Scope;
 
+/// Names a context so several values of the same type can be provided in one scope. Keys are
+/// told apart by the marker type given to [`ContextKey::new`] rather than their name, they are
+/// usually declared with `context_key!(QUERY: Signal<String>)` which creates a marker for each
+pub struct ContextKey<T> {
+    name: &'static str,
+    id: fn() -> TypeId,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ContextKey<T> {
+    pub const fn new<K: 'static>(name: &'static str) -> Self {
+        Self {
+            name,
+            id: TypeId::of::<K>,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Id of the marker type the key was declared with
+    pub fn id(&self) -> TypeId {
+        (self.id)()
+    }
+}
+
+impl<T> Clone for ContextKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for ContextKey<T> {}
+
+impl<T> std::fmt::Debug for ContextKey<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ContextKey<{}>({})",
+            std::any::type_name::<T>(),
+            self.name
+        )
+    }
+}
+
+type ContextId = (TypeId, Option<TypeId>);
+
+/// Contexts provided in one scope
+type Layer = HashMap<ContextId, Rc<dyn Any>>;
+
 #[derive(Default, Debug, Clone)]
-pub struct Contexts(pub(crate) Rc<RefCell<HashMap<(Scope, TypeId), Box<dyn Any>>>>);
+pub struct Contexts(pub(crate) Rc<RefCell<SecondaryMap<Scope, Layer>>>);
 
 impl Contexts {
-    pub(crate) fn provide_context<T: Clone + Any + 'static>(&self, scope: Scope, x: T) {
+    pub(crate) fn provide_context<T: Any + 'static>(
+        &self,
+        scope: Scope,
+        key: Option<TypeId>,
+        x: T,
+    ) {
+        let id = (TypeId::of::<T>(), key);
         let mut layers = self.0.borrow_mut();
-        layers.insert((scope, TypeId::of::<T>()), Box::new(x));
+        match layers.get_mut(scope) {
+            Some(layer) => {
+                layer.insert(id, Rc::new(x));
+            }
+            None => {
+                layers.insert(scope, HashMap::from([(id, Rc::new(x) as _)]));
+            }
+        }
     }
 
-    pub(crate) fn use_context_from_scope<T: Clone + Any + 'static>(&self, id: Scope) -> Option<T> {
+    pub(crate) fn use_context_from_scope<T: Any + 'static>(
+        &self,
+        id: Scope,
+        key: Option<TypeId>,
+    ) -> Option<Rc<T>> {
         let layers = self.0.borrow();
-        let ctx = layers.get(&(id, TypeId::of::<T>()));
-        if let Some(value) = ctx {
-            let s = value.downcast_ref::<T>().expect("Failed to downcast");
-            return Some(s.clone());
-        }
-        None
+        let ctx = layers
+            .get(id)
+            .and_then(|l| l.get(&(TypeId::of::<T>(), key)));
+        ctx.map(|value| value.clone().downcast::<T>().expect("Failed to downcast"))
+    }
+
+    /// Removes every context provided in scope
+    pub(crate) fn dispose(&self, scope: Scope) {
+        self.0.borrow_mut().remove(scope);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.borrow().values().map(|l| l.len()).sum()
     }
 }
@@ -1,6 +1,16 @@
-use std::any::{type_name, Any};
-
-use crate::{nodes::ReactiveNode, runtime::Runtime, signal::*};
+use std::{
+    any::{type_name, Any},
+    rc::Rc,
+};
+
+use crate::{
+    context::ContextKey,
+    nodes::{IntoScope, ReactiveNode, Scope},
+    recievers::IntoPropagation,
+    runtime::Runtime,
+    signal::*,
+    stats::{LeakCheck, LeakReport, RuntimeStats},
+};
 
 thread_local! {
     static RUNTIME: Runtime = Runtime::default();
@@ -16,8 +26,7 @@ pub fn with_tracking_scope<R>(f: impl FnOnce() -> R) -> R {
         let previous = r.tracker.replace(Some(scope));
         let value = f();
         r.tracker.replace(previous);
-        r.cleanup_child_scope(scope);
-        r.nodes.dispose(scope);
+        r.dispose(scope);
         value
     })
 }
@@ -27,40 +36,155 @@ pub fn on_cleanup(f: impl FnOnce() + 'static) {
     with_runtime(|r| r.add_cleanup(r.get_current_scope(), f))
 }
 
+/// Number of nodes by kind, recievers, cleanups and context entries currently held by the runtime
+pub fn runtime_stats() -> RuntimeStats {
+    with_runtime(|r| r.stats())
+}
+
+/// Reports node, reciever and cleanup counts that grow on each of `frames` consecutive frames
+pub fn enable_leak_check(frames: usize) {
+    with_runtime(|r| r.leak_check.replace(Some(LeakCheck::new(frames))));
+}
+
+pub fn disable_leak_check() {
+    with_runtime(|r| r.leak_check.replace(None));
+}
+
+/// Call once per frame, logs and returns a report when the graph keeps growing
+pub fn check_leaks() -> Option<LeakReport> {
+    with_runtime(|r| r.check_leaks())
+}
+
+/// Draw the views again on the next frame, for render closures reading state outside of signals
+pub fn request_redraw() {
+    with_runtime(|r| r.scheduler.request_redraw())
+}
+
 /// Context
-pub fn provide_context<T: Clone + Any + 'static>(x: T) {
-    with_runtime(|r| r.context.provide_context(r.get_current_scope(), x))
+pub fn provide_context<T: Any + 'static>(x: T) {
+    with_runtime(|r| r.context.provide_context(r.get_current_scope(), None, x))
 }
 
 pub fn use_context_option<T: Clone + Any + 'static>() -> Option<T> {
-    with_runtime(|r| r.get_context(r.get_current_scope()))
+    use_context_rc_option::<T>().map(|v| (*v).clone())
 }
 
 pub fn use_context<T: Clone + Any + 'static>() -> T {
-    use_context_option().expect(
-        format!(
-            "Missing {} in parent scope {:?}",
-            type_name::<T>(),
-            with_runtime(|r| r.get_current_scope())
-        )
-        .as_str(),
+    expect_context()
+}
+
+pub fn use_context_or_default<T: Clone + Default + Any + 'static>() -> T {
+    use_context_option().unwrap_or_default()
+}
+
+/// Like [`use_context`] but panics listing every scope that was searched
+pub fn expect_context<T: Clone + Any + 'static>() -> T {
+    use_context_option().unwrap_or_else(|| missing_context::<T>(None))
+}
+
+/// Shares the provided value without requiring `T: Clone`
+pub fn use_context_rc_option<T: Any + 'static>() -> Option<Rc<T>> {
+    with_runtime(|r| r.get_context(r.get_current_scope(), None))
+}
+
+pub fn use_context_rc<T: Any + 'static>() -> Rc<T> {
+    use_context_rc_option().unwrap_or_else(|| missing_context::<T>(None))
+}
+
+pub fn provide_keyed_context<T: Any + 'static>(key: ContextKey<T>, x: T) {
+    with_runtime(|r| {
+        r.context
+            .provide_context(r.get_current_scope(), Some(key.id()), x)
+    })
+}
+
+pub fn use_keyed_context_option<T: Clone + Any + 'static>(key: ContextKey<T>) -> Option<T> {
+    with_runtime(|r| r.get_context::<T>(r.get_current_scope(), Some(key.id())))
+        .map(|v| (*v).clone())
+}
+
+pub fn use_keyed_context<T: Clone + Any + 'static>(key: ContextKey<T>) -> T {
+    use_keyed_context_option(key).unwrap_or_else(|| missing_context::<T>(Some(key.name())))
+}
+
+/// Provides a new signal holding value, reads of the signal from descendants are tracked
+pub fn provide_signal_context<T: 'static>(value: T) -> Signal<T> {
+    let signal = create_signal(value);
+    provide_context(signal.clone());
+    signal
+}
+
+pub fn use_signal_context<T: 'static>() -> Signal<T> {
+    use_context::<Signal<T>>()
+}
+
+fn missing_context<T>(key: Option<&'static str>) -> ! {
+    let chain = with_runtime(|r| r.scope_chain(r.get_current_scope()));
+    let chain = chain.iter().map(|s| format!("{:?}", s)).collect::<Vec<_>>();
+    let key = key.map(|k| format!(" ({})", k)).unwrap_or_default();
+    panic!(
+        "Missing context {}{}, searched scopes {}",
+        type_name::<T>(),
+        key,
+        chain.join(" -> ")
     )
 }
 
 /// Messages
-pub fn on<T: 'static>(f: impl Fn(&T) + 'static) {
-    with_runtime(|r| r.recievers.create_reciever(r.get_current_scope(), f));
+///
+/// Recievers may return [`Propagation::Stop`] to keep the message from reaching any
+/// reciever after them
+pub fn on<T: 'static, R: IntoPropagation>(f: impl Fn(&T) -> R + 'static) {
+    with_runtime(|r| {
+        r.recievers
+            .create_reciever(r.get_current_scope(), move |v| f(v).into_propagation())
+    });
+}
+
+/// Calls f with every message of type T sent or emitted from the current scope or any of its
+/// descendants, before any reciever gets it and whether or not one stops it
+pub fn watch<T: 'static>(f: impl Fn(&T) + 'static) {
+    with_runtime(|r| r.recievers.create_watcher(r.get_current_scope(), f));
 }
 
 pub fn send_boxed(message: &Box<dyn Any>) {
     with_runtime(|r| r.send(r.get_current_scope(), message, true));
 }
 
+/// Sends a message to the current scope and its descendants. A reciever that is still
+/// handling a message, for example one sending the type it handles, does not get the messages
+/// sent while it runs
 pub fn send<T: Any + 'static>(message: T) {
     let message = Box::new(message) as Box<dyn Any>;
     send_boxed(&message);
 }
 
+pub fn send_boxed_to(scope: Scope, message: &Box<dyn Any>) {
+    with_runtime(|r| r.send(scope, message, true));
+}
+
+/// Sends a message to scope and its descendants instead of the current scope
+pub fn send_to<T: Any + 'static>(scope: impl IntoScope, message: T) {
+    let message = Box::new(message) as Box<dyn Any>;
+    send_boxed_to(scope.into_scope(), &message);
+}
+
+pub fn emit_boxed(message: &Box<dyn Any>) {
+    with_runtime(|r| r.emit(r.get_current_scope(), message));
+}
+
+/// Bubbles a message from the current scope up through its ancestors
+pub fn emit<T: Any + 'static>(message: T) {
+    let message = Box::new(message) as Box<dyn Any>;
+    emit_boxed(&message);
+}
+
+/// Id owned by the current scope and disposed with it, tells apart the timers, keymaps or
+/// actions created in one scope
+pub fn create_id() -> Scope {
+    with_runtime(|r| r.create_scope_node())
+}
+
 /// Signals
 pub fn create_signal<T: 'static>(value: T) -> Signal<T> {
     let scope = with_runtime(|r| r.create_value_node(Box::new(value)));
@@ -77,7 +201,7 @@ pub fn create_selector<T: std::fmt::Debug + PartialEq + 'static>(
 ) -> ReadSignal<T> {
     let scope = with_runtime(|r| {
         r.create_cb_node(move |previous| {
-            let previous = previous.map(|v| v.downcast_ref::<T>()).flatten();
+            let previous = previous.and_then(|v| v.downcast_ref::<T>());
             let next = f();
             match Some(&next) != previous {
                 true => Some(Box::new(next)),
@@ -90,9 +214,16 @@ pub fn create_selector<T: std::fmt::Debug + PartialEq + 'static>(
 
 #[cfg(test)]
 mod tests {
-    use std::{cell::Cell, rc::Rc};
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
 
-    use crate::environment::{with_runtime, with_tracking_scope};
+    use crate::{
+        context_key,
+        environment::{with_runtime, with_tracking_scope},
+        recievers::Propagation,
+    };
 
     use super::*;
 
@@ -246,6 +377,244 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_stats_bounded_on_recompute() {
+        with_tracking_scope(|| {
+            let trig = create_signal(0);
+            create_memo(move || {
+                trig.get();
+                create_signal(0);
+                on(move |_: &usize| {});
+                on_cleanup(|| {});
+            });
+            let stats = runtime_stats();
+            assert_eq!(stats.computed_nodes, 1);
+            assert_eq!(stats.recievers, 1);
+            assert_eq!(stats.cleanups, 1);
+            for i in 1..10 {
+                trig.set(i);
+            }
+            assert_eq!(runtime_stats(), stats);
+        });
+        assert_eq!(runtime_stats().nodes(), 0);
+    }
+
+    #[test]
+    fn test_leak_check() {
+        with_tracking_scope(|| {
+            enable_leak_check(3);
+            for _ in 0..3 {
+                create_signal(0);
+                assert_eq!(check_leaks(), None);
+            }
+            create_signal(0);
+            let report = check_leaks().expect("Reports growing nodes");
+            assert_eq!(report.grown, vec!["nodes"]);
+            assert_eq!(report.after.nodes() - report.before.nodes(), 3);
+            assert_eq!(check_leaks(), None, "Stops reporting once bounded");
+            disable_leak_check();
+        })
+    }
+
+    #[test]
+    fn test_context_shadowing() {
+        with_tracking_scope(|| {
+            provide_context("outer");
+            provide_context(1_usize);
+            let inner = create_memo(|| {
+                provide_context("inner");
+                let nested = create_memo(|| (use_context::<&str>(), use_context::<usize>()));
+                (use_context::<&str>(), nested.get())
+            });
+            assert_eq!(inner.get(), ("inner", ("inner", 1)));
+            assert_eq!(use_context::<&str>(), "outer");
+
+            provide_context("updated");
+            assert_eq!(use_context::<&str>(), "updated", "Overrides in same scope");
+            assert_eq!(use_context_or_default::<String>(), "");
+        })
+    }
+
+    #[test]
+    fn test_context_disposed_with_scope() {
+        with_tracking_scope(|| {
+            let trig = create_signal(0);
+            let found = create_signal(None);
+            create_memo(move || match trig.get() {
+                0 => provide_context(10_usize),
+                _ => found.set(use_context_option::<usize>()),
+            });
+            assert_eq!(runtime_stats().contexts, 1);
+            trig.set(1);
+            assert_eq!(found.get(), None, "Context from previous run is removed");
+            assert_eq!(runtime_stats().contexts, 0);
+        });
+        assert_eq!(runtime_stats().contexts, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing context usize, searched scopes")]
+    fn test_expect_context_lists_scopes() {
+        with_tracking_scope(|| {
+            create_memo(expect_context::<usize>);
+        })
+    }
+
+    #[test]
+    fn test_context_without_clone() {
+        struct Service(usize);
+        with_tracking_scope(|| {
+            provide_context(Service(10));
+            let value = create_memo(|| use_context_rc::<Service>().0);
+            assert_eq!(value.get(), 10);
+            assert!(Rc::ptr_eq(
+                &use_context_rc::<Service>(),
+                &use_context_rc::<Service>()
+            ));
+        })
+    }
+
+    #[test]
+    fn test_keyed_context() {
+        context_key!(FIRST: Signal<String>);
+        context_key!(SECOND: Signal<String>);
+        with_tracking_scope(|| {
+            provide_keyed_context(FIRST, create_signal("a".to_string()));
+            provide_keyed_context(SECOND, create_signal("b".to_string()));
+            let joined =
+                create_memo(|| use_keyed_context(FIRST).get() + &use_keyed_context(SECOND).get());
+            assert_eq!(joined.get(), "ab");
+            use_keyed_context(SECOND).set("c".to_string());
+            assert_eq!(joined.get(), "ac");
+            assert!(use_context_option::<Signal<String>>().is_none());
+        })
+    }
+
+    #[test]
+    fn test_keys_with_same_name_kept_apart() {
+        struct First;
+        struct Second;
+        const FIRST: ContextKey<usize> = ContextKey::new::<First>("count");
+        const SECOND: ContextKey<usize> = ContextKey::new::<Second>("count");
+        with_tracking_scope(|| {
+            provide_keyed_context(FIRST, 1);
+            provide_keyed_context(SECOND, 2);
+            assert_eq!(use_keyed_context(FIRST), 1);
+            assert_eq!(use_keyed_context(SECOND), 2);
+        })
+    }
+
+    #[test]
+    fn test_signal_context_tracked() {
+        with_tracking_scope(|| {
+            let count = provide_signal_context(1);
+            let doubled = create_memo(|| use_signal_context::<i32>().get() * 2);
+            count.set(2);
+            assert_eq!(doubled.get(), 4);
+        })
+    }
+
+    #[test]
+    fn test_recievers_emit_bubbles() {
+        with_tracking_scope(|| {
+            let recieved = Rc::new(RefCell::new(vec![]));
+            let recieved_c = recieved.clone();
+            on(move |ev: &&str| recieved_c.borrow_mut().push(("root", *ev)));
+            let recieved_c = recieved.clone();
+            let child = create_memo(move || {
+                let recieved_c = recieved_c.clone();
+                on(move |ev: &&str| recieved_c.borrow_mut().push(("child", *ev)));
+                create_memo(move || {
+                    on(move |ev: &&str| match *ev {
+                        "stop" => Propagation::Stop,
+                        _ => Propagation::Continue,
+                    });
+                    with_runtime(|r| r.get_current_scope())
+                })
+            });
+            let grandchild = child.get().get();
+            with_runtime(|r| r.with_tracking_scope(grandchild, || emit("deleted")));
+            assert_eq!(
+                *recieved.borrow(),
+                vec![("child", "deleted"), ("root", "deleted")]
+            );
+            with_runtime(|r| r.with_tracking_scope(grandchild, || emit("stop")));
+            assert_eq!(recieved.borrow().len(), 2, "Stopped before ancestors");
+        })
+    }
+
+    #[test]
+    fn test_watch_sees_descendant_messages() {
+        with_tracking_scope(|| {
+            let watched = Rc::new(RefCell::new(vec![]));
+            let watched_c = watched.clone();
+            watch(move |ev: &&str| watched_c.borrow_mut().push(*ev));
+            let child = create_memo(|| {
+                on(|_: &&str| Propagation::Stop);
+                with_runtime(|r| r.get_current_scope())
+            });
+            let child = child.get();
+            send_to(child, "sent");
+            with_runtime(|r| r.with_tracking_scope(child, || emit("emitted")));
+            send_to(child, 1);
+            assert_eq!(*watched.borrow(), vec!["sent", "emitted"]);
+        })
+    }
+
+    #[test]
+    fn test_recievers_send_order_and_stop() {
+        with_tracking_scope(|| {
+            let recieved = Rc::new(RefCell::new(vec![]));
+            let recieved_c = recieved.clone();
+            let log = Rc::new(move |name: &'static str| {
+                let recieved_c = recieved_c.clone();
+                on(move |ev: &usize| {
+                    recieved_c.borrow_mut().push(name);
+                    match *ev == 1 && name == "a.1" {
+                        true => Propagation::Stop,
+                        false => Propagation::Continue,
+                    }
+                })
+            });
+            let log_c = log.clone();
+            create_memo(move || {
+                log_c("a");
+                let log_c = log_c.clone();
+                create_memo(move || log_c("a.1"));
+            });
+            let log_c = log.clone();
+            create_memo(move || log_c("b"));
+            log("root");
+
+            send(0_usize);
+            assert_eq!(*recieved.borrow(), vec!["root", "a", "a.1", "b"]);
+            recieved.borrow_mut().clear();
+            send(1_usize);
+            assert_eq!(*recieved.borrow(), vec!["root", "a", "a.1"]);
+        })
+    }
+
+    #[test]
+    fn test_send_to_view() {
+        use crate::ratatui::{component, widget_ref, RatView};
+
+        fn counter(count: Signal<usize>) -> RatView {
+            on(move |ev: &usize| count.update(|c| *c += ev));
+            widget_ref(|| "")
+        }
+
+        with_tracking_scope(|| {
+            let (a, b) = (create_signal(0), create_signal(0));
+            let view_a = component(move || counter(a));
+            let view_b = component(move || counter(b));
+            view_a.send(1_usize);
+            send_to(view_b, 2_usize);
+            assert_eq!((a.get(), b.get()), (1, 2));
+            send(3_usize);
+            assert_eq!((a.get(), b.get()), (4, 5));
+        })
+    }
+
     #[test]
     fn test_recievers_cleanup_on_send() {
         fn even(f: impl Fn((String, usize)) + 'static) {
@@ -277,4 +646,62 @@ mod tests {
             assert_eq!(result.get(), ("even".to_string(), 10));
         })
     }
+
+    #[test]
+    fn test_recievers_reentrant_send() {
+        with_tracking_scope(|| {
+            let (echoed, nested) = (create_signal(0), create_signal(0));
+            on(move |ev: &usize| {
+                echoed.update(|v| *v += 1);
+                send(*ev + 1);
+            });
+            on(move |ev: &usize| nested.update(|v| *v += ev));
+
+            send(1_usize);
+            assert_eq!(echoed.get(), 1, "Reciever not called by its own send");
+            assert_eq!(nested.get(), 2 + 1, "Other recievers get both messages");
+        })
+    }
+
+    #[test]
+    fn test_recievers_called_after_panic() {
+        with_tracking_scope(|| {
+            let calls = Rc::new(Cell::new(0));
+            let calls_c = calls.clone();
+            on(move |_: &usize| {
+                calls_c.set(calls_c.get() + 1);
+                if calls_c.get() == 1 {
+                    panic!("first call");
+                }
+            });
+            let result = std::panic::catch_unwind(|| send(1_usize));
+            assert!(result.is_err());
+            send(2_usize);
+            assert_eq!(calls.get(), 2);
+        })
+    }
+
+    #[test]
+    fn test_recievers_disposed_during_send() {
+        with_tracking_scope(|| {
+            let visible = create_signal(true);
+            let recieved = Rc::new(RefCell::new(vec![]));
+            let recieved_c = recieved.clone();
+            create_memo(move || {
+                if !visible.get() {
+                    return;
+                }
+                let (first, second) = (recieved_c.clone(), recieved_c.clone());
+                on(move |_: &&str| {
+                    first.borrow_mut().push("first");
+                    visible.set(false);
+                });
+                on(move |_: &&str| second.borrow_mut().push("second"));
+            });
+
+            send("close");
+            send("close");
+            assert_eq!(*recieved.borrow(), vec!["first"]);
+        })
+    }
 }
@@ -0,0 +1,95 @@
+use std::marker::PhantomData;
+
+use crossterm::event::{Event, KeyEventKind};
+use ratatui::layout::Rect;
+
+use crate::{environment::*, mouse::send_mouse, signal::*};
+
+/// Terminal was resized to the area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resize(pub Rect);
+
+/// Text pasted into the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paste(pub String);
+
+/// Terminal window gained (true) or lost (false) focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalFocus(pub bool);
+
+/// Sends a crossterm event as a typed message, mouse events go to the view under the cursor.
+/// Only key presses are sent as a [`KeyEvent`](crossterm::event::KeyEvent), release and repeat
+/// events are dropped
+pub fn send_event(event: Event) {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => send(key),
+        Event::Key(_) => {}
+        Event::Resize(width, height) => {
+            let area = Rect::new(0, 0, width, height);
+            with_runtime(|r| r.terminal_size.set(area));
+            send(Resize(area))
+        }
+        Event::Paste(text) => send(Paste(text)),
+        Event::FocusGained => send(TerminalFocus(true)),
+        Event::FocusLost => send(TerminalFocus(false)),
+        Event::Mouse(mouse) => send_mouse(mouse),
+    }
+}
+
+/// Size of the terminal updated on every [`Resize`], starts at the size the app runner or
+/// [`TestApp`](crate::testing::TestApp) last saw
+pub fn use_terminal_size() -> ReadSignal<Rect> {
+    let size = create_signal(with_runtime(|r| r.terminal_size.get()));
+    on(move |Resize(area): &Resize| size.set(*area));
+    ReadSignal(size.0, PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyEventState, KeyModifiers};
+
+    fn key(code: KeyCode, kind: KeyEventKind) -> Event {
+        Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::CONTROL,
+            kind,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    #[test]
+    fn test_only_key_presses_sent() {
+        with_tracking_scope(|| {
+            let keys = create_signal(vec![]);
+            let keys_c = keys.clone();
+            on(move |key: &KeyEvent| keys_c.update(|k| k.push((key.code, key.modifiers))));
+            send_event(key(KeyCode::Char('a'), KeyEventKind::Press));
+            send_event(key(KeyCode::Char('a'), KeyEventKind::Repeat));
+            send_event(key(KeyCode::Char('a'), KeyEventKind::Release));
+            assert_eq!(
+                keys.get(),
+                vec![(KeyCode::Char('a'), KeyModifiers::CONTROL)]
+            );
+        })
+    }
+
+    #[test]
+    fn test_terminal_events() {
+        with_tracking_scope(|| {
+            let size = use_terminal_size();
+            let pasted = create_signal(String::new());
+            let focused = create_signal(true);
+            let pasted_c = pasted.clone();
+            on(move |Paste(text): &Paste| pasted_c.update(|p| p.push_str(text)));
+            on(move |TerminalFocus(f): &TerminalFocus| focused.set(*f));
+
+            send_event(Event::Resize(80, 24));
+            send_event(Event::Paste("hello".into()));
+            send_event(Event::FocusLost);
+            assert_eq!(size.get(), Rect::new(0, 0, 80, 24));
+            assert_eq!(pasted.get(), "hello");
+            assert!(!focused.get());
+        })
+    }
+}
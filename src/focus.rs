@@ -0,0 +1,397 @@
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use slotmap::SecondaryMap;
+
+use crate::{
+    environment::*,
+    nodes::{IntoScope, Scope},
+    recievers::Propagation,
+    signal::*,
+};
+
+#[derive(Default)]
+struct FocusState {
+    /// Focusable scopes in the order they were registered
+    order: Vec<Scope>,
+    signals: SecondaryMap<Scope, Signal<bool>>,
+    focused: Option<Scope>,
+    /// Scopes whose owner is re-running, unregistered unless they register again
+    released: HashSet<Scope>,
+    /// Message types delivered to the focused scope and its ancestors, counted per route
+    routed: HashMap<TypeId, usize>,
+    routes: SecondaryMap<Scope, Vec<TypeId>>,
+}
+
+#[derive(Default, Clone)]
+pub struct Focus(Rc<RefCell<FocusState>>);
+
+impl Focus {
+    /// Registers scope keeping its place in the order when it registers again
+    pub(crate) fn register(&self, scope: Scope, signal: Signal<bool>) {
+        let mut state = self.0.borrow_mut();
+        if !state.released.remove(&scope) {
+            state.order.push(scope);
+        }
+        state.signals.insert(scope, signal);
+    }
+
+    /// Marks scope to be unregistered by [`Focus::sweep`] unless it registers again first
+    pub(crate) fn release(&self, scope: Scope) {
+        let mut state = self.0.borrow_mut();
+        if state.signals.remove(scope).is_some() {
+            state.released.insert(scope);
+        }
+    }
+
+    /// Unregisters scope if it was released and not registered again, returning the scope
+    /// that should take over its focus
+    pub(crate) fn sweep(&self, scope: Scope) -> Option<Scope> {
+        if !self.0.borrow_mut().released.remove(&scope) {
+            return None;
+        }
+        self.unregister(scope)
+    }
+
+    /// Removes scope returning the scope that should take over its focus
+    pub(crate) fn unregister(&self, scope: Scope) -> Option<Scope> {
+        let mut state = self.0.borrow_mut();
+        let index = state.order.iter().position(|s| *s == scope)?;
+        state.order.remove(index);
+        state.signals.remove(scope);
+        if state.focused != Some(scope) {
+            return None;
+        }
+        state.focused = None;
+        match state.order.is_empty() {
+            true => None,
+            false => Some(state.order[index.min(state.order.len() - 1)]),
+        }
+    }
+
+    /// Sets the focused scope returning the signals of the previous and next focused scopes
+    pub(crate) fn set_focused(
+        &self,
+        scope: Option<Scope>,
+    ) -> (Option<Signal<bool>>, Option<Signal<bool>>) {
+        let mut state = self.0.borrow_mut();
+        let scope = scope.filter(|s| state.signals.contains_key(*s));
+        let previous = std::mem::replace(&mut state.focused, scope);
+        if previous == scope {
+            return (None, None);
+        }
+        let previous = previous.and_then(|s| state.signals.get(s).copied());
+        let next = scope.and_then(|s| state.signals.get(s).copied());
+        (previous, next)
+    }
+
+    pub(crate) fn focused(&self) -> Option<Scope> {
+        self.0.borrow().focused
+    }
+
+    pub(crate) fn signals(&self) -> Vec<Signal<bool>> {
+        self.0.borrow().signals.values().copied().collect()
+    }
+
+    pub(crate) fn focusables(&self) -> Vec<Scope> {
+        self.0.borrow().order.clone()
+    }
+
+    pub(crate) fn add_route(&self, scope: Scope, type_id: TypeId) {
+        let mut state = self.0.borrow_mut();
+        *state.routed.entry(type_id).or_default() += 1;
+        match state.routes.get_mut(scope) {
+            Some(routes) => routes.push(type_id),
+            None => {
+                state.routes.insert(scope, vec![type_id]);
+            }
+        }
+    }
+
+    /// Removes the routes added by scope
+    pub(crate) fn dispose_routes(&self, scope: Scope) {
+        let mut state = self.0.borrow_mut();
+        for type_id in state.routes.remove(scope).unwrap_or_default() {
+            if let Some(count) = state.routed.get_mut(&type_id) {
+                *count -= 1;
+                if *count == 0 {
+                    state.routed.remove(&type_id);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn is_routed(&self, type_id: TypeId) -> bool {
+        self.0.borrow().routed.contains_key(&type_id)
+    }
+}
+
+/// Registered with [`use_focus`], tracks whether the component has focus
+#[derive(Clone, Copy)]
+pub struct FocusHandle {
+    scope: Scope,
+    focused: Signal<bool>,
+}
+
+impl FocusHandle {
+    pub fn focused(&self) -> ReadSignal<bool> {
+        ReadSignal(self.focused.0, PhantomData)
+    }
+    pub fn is_focused(&self) -> bool {
+        self.focused.get()
+    }
+    pub fn focus(&self) {
+        focus(self.scope)
+    }
+}
+
+impl IntoScope for FocusHandle {
+    fn into_scope(&self) -> Scope {
+        self.scope
+    }
+}
+
+/// Cycles focus between the focusable components created under it
+#[derive(Clone, Copy)]
+pub struct FocusScope(Scope);
+
+impl FocusScope {
+    pub fn focus_next(&self) {
+        cycle_focus(self.0, 1)
+    }
+    pub fn focus_prev(&self) {
+        cycle_focus(self.0, -1)
+    }
+
+    fn on_key(&self, code: KeyCode) -> Propagation {
+        match code {
+            KeyCode::Tab => self.focus_next(),
+            KeyCode::BackTab => self.focus_prev(),
+            _ => return Propagation::Continue,
+        }
+        Propagation::Stop
+    }
+}
+
+/// Registers the current scope as focusable, the first focusable to register receives focus.
+/// The registration and focus are kept when the scope re-runs and calls use_focus again
+pub fn use_focus() -> FocusHandle {
+    let scope = with_runtime(|r| r.get_current_scope());
+    let focused = create_signal(focused_scope() == Some(scope));
+    with_runtime(|r| r.focus.register(scope, focused));
+    if focused_scope().is_none() {
+        focus(scope);
+    }
+    on_cleanup(move || with_runtime(|r| r.focus.release(scope)));
+    FocusHandle { scope, focused }
+}
+
+/// Routes key messages to the focused component and its ancestors, Tab and Shift-Tab
+/// move focus between the focusable components created under the current scope
+pub fn create_focus_scope() -> FocusScope {
+    let scope = FocusScope(with_runtime(|r| r.get_current_scope()));
+    route_to_focus::<KeyCode>();
+    route_to_focus::<KeyEvent>();
+    on(move |code: &KeyCode| scope.on_key(*code));
+    on(move |key: &KeyEvent| scope.on_key(key.code));
+    provide_context(scope);
+    scope
+}
+
+/// Messages of type T sent to an ancestor of the focused scope are delivered only to the
+/// ancestors of the focused scope, outermost first, and then the focused scope, until the
+/// current scope re-runs or is disposed
+pub fn route_to_focus<T: Any + 'static>() {
+    with_runtime(|r| r.focus.add_route(r.get_current_scope(), TypeId::of::<T>()));
+}
+
+pub fn focused_scope() -> Option<Scope> {
+    with_runtime(|r| r.focus.focused())
+}
+
+/// Like [`focused_scope`] but the current scope re-runs when focus moves
+pub fn track_focused_scope() -> Option<Scope> {
+    for focused in with_runtime(|r| r.focus.signals()) {
+        focused.get();
+    }
+    focused_scope()
+}
+
+pub fn focus(scope: impl IntoScope) {
+    let (previous, next) = with_runtime(|r| r.focus.set_focused(Some(scope.into_scope())));
+    if let Some(previous) = previous {
+        previous.set(false);
+    }
+    if let Some(next) = next {
+        next.set(true);
+    }
+}
+
+fn cycle_focus(within: Scope, step: isize) {
+    let focusables = with_runtime(|r| {
+        let focusables = r.focus.focusables().into_iter();
+        focusables
+            .filter(|s| r.scope_chain(*s).contains(&within))
+            .collect::<Vec<_>>()
+    });
+    if focusables.is_empty() {
+        return;
+    }
+    let len = focusables.len() as isize;
+    let next = match focused_scope().and_then(|f| focusables.iter().position(|s| *s == f)) {
+        Some(i) => (i as isize + step).rem_euclid(len),
+        None => 0,
+    };
+    focus(focusables[next as usize]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratatui::{component, widget_ref, RatView};
+
+    fn input(count: Signal<usize>) -> RatView {
+        let focus = use_focus();
+        on(move |key: &KeyCode| {
+            if let KeyCode::Char(_) = key {
+                count.update(|c| *c += 1)
+            }
+        });
+        widget_ref(move || focus.is_focused().to_string())
+    }
+
+    #[test]
+    fn test_keys_routed_to_focused() {
+        with_tracking_scope(|| {
+            let (app, a, b) = (create_signal(0), create_signal(0), create_signal(0));
+            let first = create_signal(None);
+            component(move || {
+                let scope = create_focus_scope();
+                on(move |_: &KeyCode| app.update(|c| *c += 1));
+                first.set(Some(component(move || input(a))));
+                component(move || input(b));
+                scope.focus_next();
+                widget_ref(|| "")
+            });
+            send(KeyCode::Char('x'));
+            assert_eq!((app.get(), a.get(), b.get()), (1, 0, 1));
+
+            send(KeyCode::Tab);
+            send(KeyCode::Char('x'));
+            assert_eq!((app.get(), a.get(), b.get()), (2, 1, 1));
+
+            send(KeyCode::BackTab);
+            send(KeyCode::BackTab);
+            assert_eq!(focused_scope(), Some(first.get().unwrap().into_scope()));
+            assert_eq!(app.get(), 2, "Tab stops at the focus scope");
+        })
+    }
+
+    #[test]
+    fn test_routed_keys_reach_ancestors_first() {
+        with_tracking_scope(|| {
+            let order = Rc::new(RefCell::new(vec![]));
+            let push = |name| {
+                let order = order.clone();
+                move |_: &KeyCode| order.borrow_mut().push(name)
+            };
+            create_focus_scope();
+            on(push("root"));
+            let (parent, focused) = (push("parent"), push("focused"));
+            component(move || {
+                on(parent.clone());
+                let focused = focused.clone();
+                component(move || {
+                    use_focus();
+                    on(focused.clone());
+                    widget_ref(|| "")
+                })
+            });
+            send(KeyCode::Char('x'));
+            assert_eq!(*order.borrow(), vec!["root", "parent", "focused"]);
+        })
+    }
+
+    #[test]
+    fn test_focus_moves_when_focused_disposed() {
+        with_tracking_scope(|| {
+            let show = create_signal(true);
+            let focused = create_signal(None);
+            create_focus_scope();
+            create_memo(move || {
+                if show.get() {
+                    use_focus();
+                }
+            });
+            let handle = use_focus();
+            create_memo(move || focused.set(Some(handle.focused().get())));
+            assert_eq!(focused.get(), Some(false));
+            show.set(false);
+            assert_eq!(focused.get(), Some(true));
+            assert_eq!(focused_scope(), Some(handle.into_scope()));
+        })
+    }
+
+    #[test]
+    fn test_focus_kept_when_scope_reruns() {
+        with_tracking_scope(|| {
+            let rerun = create_signal(0);
+            let scope = create_focus_scope();
+            let first = component(move || {
+                rerun.get();
+                let focus = use_focus();
+                widget_ref(move || focus.is_focused().to_string())
+            });
+            let second = component(move || {
+                let focus = use_focus();
+                widget_ref(move || focus.is_focused().to_string())
+            });
+            scope.focus_next();
+            assert_eq!(focused_scope(), Some(second.into_scope()));
+
+            rerun.set(1);
+            assert_eq!(focused_scope(), Some(second.into_scope()));
+            scope.focus_next();
+            assert_eq!(focused_scope(), Some(first.into_scope()), "Order kept");
+
+            rerun.set(2);
+            assert_eq!(focused_scope(), Some(first.into_scope()), "Focus kept");
+        })
+    }
+
+    #[test]
+    fn test_routes_removed_with_scope() {
+        with_tracking_scope(|| {
+            let show = create_signal(true);
+            let count = create_signal(0);
+            component(move || {
+                on(move |_: &KeyCode| count.update(|c| *c += 1));
+                widget_ref(|| "")
+            });
+            create_memo(move || {
+                if show.get() {
+                    create_focus_scope();
+                    component(|| {
+                        use_focus();
+                        widget_ref(|| "")
+                    });
+                }
+            });
+            send(KeyCode::Char('x'));
+            assert_eq!(count.get(), 0, "Routed to the focused component");
+
+            show.set(false);
+            assert!(!with_runtime(|r| r
+                .focus
+                .is_routed(TypeId::of::<KeyCode>())));
+            send(KeyCode::Char('x'));
+            assert_eq!(count.get(), 1);
+        })
+    }
+}
@@ -0,0 +1,817 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MediaKeyCode, ModifierKeyCode,
+};
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Widget},
+};
+
+use crate::{
+    context_key,
+    environment::*,
+    focus::track_focused_scope,
+    nodes::Scope,
+    ratatui::{render, RatView},
+    recievers::Propagation,
+    signal::*,
+    tasks::TaskRunner,
+};
+
+/// How long a partially typed chord waits for its next key
+pub const CHORD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Mode keymaps created with [`use_keymap_in`] compare against when no mode was provided
+pub const DEFAULT_KEY_MODE: &str = "normal";
+
+context_key!(KEY_MODE: Signal<&'static str>);
+
+/// Keys without a value by the name they are displayed and parsed as
+const NAMED_KEYS: [(KeyCode, &str); 23] = [
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::BackTab, "backtab"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "pageup"),
+    (KeyCode::PageDown, "pagedown"),
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Insert, "insert"),
+    (KeyCode::Null, "null"),
+    (KeyCode::CapsLock, "capslock"),
+    (KeyCode::ScrollLock, "scrolllock"),
+    (KeyCode::NumLock, "numlock"),
+    (KeyCode::PrintScreen, "printscreen"),
+    (KeyCode::Pause, "pause"),
+    (KeyCode::Menu, "menu"),
+    (KeyCode::KeypadBegin, "keypadbegin"),
+];
+
+/// Displayed and parsed as `media-` followed by the lowercase name, like `media-playpause`
+const MEDIA_KEYS: [MediaKeyCode; 13] = [
+    MediaKeyCode::Play,
+    MediaKeyCode::Pause,
+    MediaKeyCode::PlayPause,
+    MediaKeyCode::Reverse,
+    MediaKeyCode::Stop,
+    MediaKeyCode::FastForward,
+    MediaKeyCode::Rewind,
+    MediaKeyCode::TrackNext,
+    MediaKeyCode::TrackPrevious,
+    MediaKeyCode::Record,
+    MediaKeyCode::LowerVolume,
+    MediaKeyCode::RaiseVolume,
+    MediaKeyCode::MuteVolume,
+];
+
+/// Displayed and parsed as `modifier-` followed by the lowercase name, like
+/// `modifier-leftshift`
+const MODIFIER_KEYS: [ModifierKeyCode; 14] = [
+    ModifierKeyCode::LeftShift,
+    ModifierKeyCode::LeftControl,
+    ModifierKeyCode::LeftAlt,
+    ModifierKeyCode::LeftSuper,
+    ModifierKeyCode::LeftHyper,
+    ModifierKeyCode::LeftMeta,
+    ModifierKeyCode::RightShift,
+    ModifierKeyCode::RightControl,
+    ModifierKeyCode::RightAlt,
+    ModifierKeyCode::RightSuper,
+    ModifierKeyCode::RightHyper,
+    ModifierKeyCode::RightMeta,
+    ModifierKeyCode::IsoLevel3Shift,
+    ModifierKeyCode::IsoLevel5Shift,
+];
+
+fn lowercase_name(key: impl std::fmt::Debug) -> String {
+    format!("{:?}", key).to_lowercase()
+}
+
+/// A single key with its modifiers, parsed from specs like `ctrl+s`, `shift+tab` or `f5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers;
+        let code = match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(ch.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, KeyParseError> {
+        let (modifiers, key) = match spec.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => match spec.rsplit_once('+') {
+                Some((modifiers, key)) if !key.is_empty() => (modifiers, key),
+                _ => ("", spec),
+            },
+        };
+
+        let mut mods = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            mods |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                "super" | "cmd" => KeyModifiers::SUPER,
+                _ => return Err(KeyParseError::new(spec, "unknown modifier")),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "escape" => KeyCode::Esc,
+                "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "plus" => KeyCode::Char('+'),
+                "del" => KeyCode::Delete,
+                "ins" => KeyCode::Insert,
+                name => {
+                    Self::parse_name(name).ok_or_else(|| KeyParseError::new(spec, "unknown key"))?
+                }
+            },
+        };
+        Ok(Self::new(code, mods))
+    }
+
+    fn parse_name(name: &str) -> Option<KeyCode> {
+        if let Some((code, _)) = NAMED_KEYS.iter().find(|(_, n)| *n == name) {
+            return Some(*code);
+        }
+        if let Some(media) = name.strip_prefix("media-") {
+            let key = MEDIA_KEYS.into_iter().find(|k| lowercase_name(k) == media);
+            return key.map(KeyCode::Media);
+        }
+        if let Some(modifier) = name.strip_prefix("modifier-") {
+            let key = MODIFIER_KEYS
+                .into_iter()
+                .find(|k| lowercase_name(k) == modifier);
+            return key.map(KeyCode::Modifier);
+        }
+        match name.strip_prefix('f')?.parse::<u8>() {
+            Ok(n) if n > 0 => Some(KeyCode::F(n)),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(value: KeyEvent) -> Self {
+        Self::new(value.code, value.modifiers)
+    }
+}
+
+impl std::fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+            (KeyModifiers::SUPER, "super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char('+') => write!(f, "plus"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::BackTab => write!(f, "shift+tab"),
+            KeyCode::Media(key) => write!(f, "media-{}", lowercase_name(key)),
+            KeyCode::Modifier(key) => write!(f, "modifier-{}", lowercase_name(key)),
+            code => match NAMED_KEYS.iter().find(|(c, _)| *c == code) {
+                Some((_, name)) => f.write_str(name),
+                None => write!(f, "{}", lowercase_name(code)),
+            },
+        }
+    }
+}
+
+/// Keys pressed one after another, parsed from space separated specs like `g g`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyPress>);
+
+impl KeySequence {
+    pub fn parse(spec: &str) -> Result<Self, KeyParseError> {
+        let keys = spec
+            .split_whitespace()
+            .map(KeyPress::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        match keys.is_empty() {
+            true => Err(KeyParseError::new(spec, "empty key sequence")),
+            false => Ok(Self(keys)),
+        }
+    }
+}
+
+impl std::fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.0.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", keys.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyParseError {
+    pub spec: String,
+    pub reason: &'static str,
+}
+
+impl KeyParseError {
+    fn new(spec: &str, reason: &'static str) -> Self {
+        Self {
+            spec: spec.to_string(),
+            reason,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid key `{}`: {}", self.spec, self.reason)
+    }
+}
+
+impl std::error::Error for KeyParseError {}
+
+pub struct Binding<A> {
+    pub keys: KeySequence,
+    pub action: A,
+    pub description: Option<&'static str>,
+}
+
+impl<A> Binding<A> {
+    /// Binds action to the keys of spec, for specs that are not literals like ones read from
+    /// a config file
+    pub fn new(spec: &str, action: A) -> Result<Self, KeyParseError> {
+        Ok(Self {
+            keys: KeySequence::parse(spec)?,
+            action,
+            description: None,
+        })
+    }
+
+    pub fn description(self, description: &'static str) -> Self {
+        Self {
+            description: Some(description),
+            ..self
+        }
+    }
+}
+
+/// For spec literals, panics when spec is invalid. Use [`Binding::new`] for other specs
+impl<A> From<(&'static str, A)> for Binding<A> {
+    fn from((spec, action): (&'static str, A)) -> Self {
+        Binding::new(spec, action).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// For spec literals, panics when spec is invalid. Use [`Binding::new`] for other specs
+impl<A> From<(&'static str, A, &'static str)> for Binding<A> {
+    fn from((spec, action, description): (&'static str, A, &'static str)) -> Self {
+        Binding::from((spec, action)).description(description)
+    }
+}
+
+type KeyMode = Option<(&'static str, Option<Signal<&'static str>>)>;
+
+fn mode_active(mode: KeyMode, tracked: bool) -> bool {
+    match mode {
+        Some((mode, Some(current))) if tracked => mode == current.get(),
+        Some((mode, Some(current))) => mode == current.get_untracked(),
+        Some((mode, None)) => mode == DEFAULT_KEY_MODE,
+        None => true,
+    }
+}
+
+/// Bindings of a keymap as listed by [`help_overlay`]
+#[derive(Clone)]
+pub struct KeymapInfo {
+    pub owner: Scope,
+    pub name: Option<String>,
+    mode: KeyMode,
+    pub bindings: Vec<(KeySequence, Option<&'static str>)>,
+}
+
+impl KeymapInfo {
+    pub fn mode(&self) -> Option<&'static str> {
+        self.mode.map(|(m, _)| m)
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Keymaps(Rc<RefCell<Vec<(Scope, KeymapInfo)>>>);
+
+impl Keymaps {
+    fn register(&self, id: Scope, info: KeymapInfo) {
+        self.0.borrow_mut().push((id, info));
+    }
+
+    fn unregister(&self, id: Scope) {
+        self.0.borrow_mut().retain(|(k, _)| *k != id);
+    }
+
+    fn with_info(&self, id: Scope, f: impl FnOnce(&mut KeymapInfo)) {
+        if let Some((_, info)) = self.0.borrow_mut().iter_mut().find(|(k, _)| *k == id) {
+            f(info)
+        }
+    }
+
+    fn all(&self) -> Vec<KeymapInfo> {
+        self.0
+            .borrow()
+            .iter()
+            .map(|(_, info)| info.clone())
+            .collect()
+    }
+}
+
+/// Returned from [`use_keymap`] to describe the keymap in the help overlay
+#[derive(Clone, Copy)]
+pub struct Keymap(Scope);
+
+impl Keymap {
+    /// Groups the bindings under name in [`help_overlay`]
+    pub fn named(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        with_runtime(|r| r.keymaps.with_info(self.0, |info| info.name = Some(name)));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChordTimeout(Scope, u64);
+
+struct KeymapState<A> {
+    id: Scope,
+    mode: KeyMode,
+    bindings: Vec<Binding<A>>,
+    pending: Vec<KeyPress>,
+    /// Incremented for each key so stale timeouts can be ignored
+    presses: u64,
+    last: Instant,
+    timeout: Duration,
+}
+
+impl<A: Clone + 'static> KeymapState<A> {
+    fn exact(&self) -> Option<A> {
+        let binding = self.bindings.iter().find(|b| b.keys.0 == self.pending);
+        binding.map(|b| b.action.clone())
+    }
+
+    fn is_prefix(&self) -> bool {
+        self.bindings
+            .iter()
+            .any(|b| b.keys.0.len() > self.pending.len() && b.keys.0.starts_with(&self.pending))
+    }
+
+    /// Returns the actions to dispatch and whether the key was consumed
+    fn press(&mut self, key: KeyPress) -> (Vec<A>, bool) {
+        let mut actions = vec![];
+        if !self.pending.is_empty() && self.last.elapsed() > self.timeout {
+            actions.extend(self.exact());
+            self.pending.clear();
+        }
+        self.presses += 1;
+        self.last = Instant::now();
+
+        for retry in [false, true] {
+            if retry {
+                if self.pending.len() == 1 {
+                    break;
+                }
+                self.pending.clear();
+            }
+            self.pending.push(key);
+            if self.is_prefix() {
+                return (actions, true);
+            }
+            if let Some(action) = self.exact() {
+                self.pending.clear();
+                actions.push(action);
+                return (actions, true);
+            }
+        }
+        self.pending.clear();
+        (actions, false)
+    }
+
+    fn expire(&mut self, presses: u64) -> Option<A> {
+        if presses != self.presses || self.pending.is_empty() {
+            return None;
+        }
+        let action = self.exact();
+        self.pending.clear();
+        action
+    }
+}
+
+/// Sets the active key mode for keymaps created with [`use_keymap_in`] below the current scope
+pub fn provide_key_mode(initial: &'static str) -> Signal<&'static str> {
+    let mode = create_signal(initial);
+    provide_keyed_context(KEY_MODE, mode);
+    mode
+}
+
+pub fn use_key_mode() -> Signal<&'static str> {
+    use_keyed_context(KEY_MODE)
+}
+
+/// Sends the bound action to the current scope when its keys are pressed, keys are read from
+/// [`KeyEvent`](crossterm::event::KeyEvent) messages.
+///
+/// When one binding is a prefix of another, like `g` and `g g`, the shorter binding fires
+/// once no further key arrives within [`CHORD_TIMEOUT`]
+pub fn use_keymap<A, B>(bindings: impl IntoIterator<Item = B>) -> Keymap
+where
+    A: Clone + 'static,
+    B: Into<Binding<A>>,
+{
+    create_keymap(None, bindings)
+}
+
+/// Like [`use_keymap`] but only active while [`use_key_mode`] equals mode, or while mode is
+/// [`DEFAULT_KEY_MODE`] when no mode was provided
+pub fn use_keymap_in<A, B>(mode: &'static str, bindings: impl IntoIterator<Item = B>) -> Keymap
+where
+    A: Clone + 'static,
+    B: Into<Binding<A>>,
+{
+    create_keymap(Some(mode), bindings)
+}
+
+fn create_keymap<A, B>(mode: Option<&'static str>, bindings: impl IntoIterator<Item = B>) -> Keymap
+where
+    A: Clone + 'static,
+    B: Into<Binding<A>>,
+{
+    let owner = with_runtime(|r| r.get_current_scope());
+    // Tells the timeouts of keymaps sharing a scope apart
+    let id = create_id();
+    let tasks = use_context_option::<TaskRunner>();
+    let mode = mode.map(|m| (m, use_keyed_context_option(KEY_MODE)));
+    if let Some((mode, None)) = mode {
+        tracing::warn!(
+            "Keymap for mode {} created without provide_key_mode, using {}",
+            mode,
+            DEFAULT_KEY_MODE
+        );
+    }
+    let bindings = bindings.into_iter().map(Into::into).collect::<Vec<_>>();
+
+    let info = KeymapInfo {
+        owner,
+        name: None,
+        mode,
+        bindings: bindings
+            .iter()
+            .map(|b| (b.keys.clone(), b.description))
+            .collect(),
+    };
+    with_runtime(|r| r.keymaps.register(id, info));
+    on_cleanup(move || with_runtime(|r| r.keymaps.unregister(id)));
+
+    let state = Rc::new(RefCell::new(KeymapState {
+        id,
+        mode,
+        bindings,
+        pending: vec![],
+        presses: 0,
+        last: Instant::now(),
+        timeout: CHORD_TIMEOUT,
+    }));
+
+    let state_c = state.clone();
+    let press = move |key: KeyPress| {
+        if !mode_active(state_c.borrow().mode, false) {
+            return Propagation::Continue;
+        }
+        let (actions, consumed) = state_c.borrow_mut().press(key);
+        let state = state_c.borrow();
+        if let (Some(tasks), false) = (&tasks, state.pending.is_empty()) {
+            let (timeout, presses) = (state.timeout, state.presses);
+            tasks.spawn_message(owner, async move {
+                tokio::time::sleep(timeout).await;
+                ChordTimeout(id, presses)
+            });
+        }
+        drop(state);
+        for action in actions {
+            send_to(owner, action);
+        }
+        match consumed {
+            true => Propagation::Stop,
+            false => Propagation::Continue,
+        }
+    };
+
+    // Only key events advance the chords, a KeyCode sent for the same press would count twice
+    on(move |key: &KeyEvent| match key.kind {
+        KeyEventKind::Press => press((*key).into()),
+        _ => Propagation::Continue,
+    });
+    on(move |ChordTimeout(timeout_id, presses): &ChordTimeout| {
+        if *timeout_id != state.borrow().id {
+            return;
+        }
+        let action = state.borrow_mut().expire(*presses);
+        if let Some(action) = action {
+            send_to(owner, action);
+        }
+    });
+    Keymap(id)
+}
+
+/// Keymaps in the current mode of the focused scope and its ancestors, focused first.
+/// Every keymap in the current mode when nothing is focused. The mode and focus are tracked
+pub fn active_keymaps() -> Vec<KeymapInfo> {
+    let keymaps = with_runtime(|r| r.keymaps.all());
+    let keymaps = keymaps.into_iter().filter(|k| mode_active(k.mode, true));
+    match track_focused_scope() {
+        Some(focused) => {
+            let chain = with_runtime(|r| r.scope_chain(focused));
+            let mut keymaps = keymaps
+                .filter_map(|k| Some((chain.iter().position(|s| *s == k.owner)?, k)))
+                .collect::<Vec<_>>();
+            keymaps.sort_by_key(|(depth, _)| *depth);
+            keymaps.into_iter().map(|(_, k)| k).collect()
+        }
+        None => keymaps.collect(),
+    }
+}
+
+/// Lists the bindings of [`active_keymaps`] grouped by the component that registered them
+pub fn help_overlay() -> RatView {
+    render(|area, buf| {
+        type Group = (Scope, Option<String>, Vec<(String, &'static str)>);
+        let mut groups: Vec<Group> = vec![];
+        for keymap in active_keymaps() {
+            let rows = keymap
+                .bindings
+                .iter()
+                .map(|(keys, description)| (keys.to_string(), description.unwrap_or_default()));
+            match groups.iter_mut().find(|(owner, ..)| *owner == keymap.owner) {
+                Some((_, name, group)) => {
+                    *name = name.take().or(keymap.name);
+                    group.extend(rows);
+                }
+                None => groups.push((keymap.owner, keymap.name, rows.collect())),
+            }
+        }
+
+        let width = groups
+            .iter()
+            .flat_map(|g| g.2.iter().map(|r| r.0.len()))
+            .max();
+        let mut text = vec![];
+        for (_, name, rows) in groups {
+            if !text.is_empty() {
+                text.push(Line::default());
+            }
+            text.push(Line::from(name.unwrap_or("Keys".into())).underlined());
+            text.extend(rows.into_iter().map(|(keys, description)| {
+                let keys = format!("{:<1$}  ", keys, width.unwrap_or_default());
+                Line::from(vec![Span::from(keys).bold(), Span::from(description)])
+            }));
+        }
+        let block = Block::default()
+            .borders(Borders::all())
+            .padding(Padding::horizontal(1))
+            .title("Help");
+        Clear.render(area, buf);
+        Paragraph::new(text).block(block).render(area, buf);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Action {
+        Save,
+        Top,
+        Down,
+        Insert,
+    }
+
+    fn press(spec: &str) {
+        let key = KeyPress::parse(spec).unwrap();
+        send(KeyEvent::new(key.code, key.modifiers));
+    }
+
+    #[test]
+    fn test_parse_key_specs() {
+        let ctrl_s = KeyPress::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(KeyPress::parse("ctrl+s"), Ok(ctrl_s));
+        assert_eq!(
+            KeyPress::parse("Ctrl+S").map(|k| k.to_string()),
+            Ok("ctrl+S".into())
+        );
+        assert_eq!(KeyPress::parse("shift+g"), KeyPress::parse("G"));
+        assert_eq!(KeyPress::parse("shift+tab"), KeyPress::parse("backtab"));
+        assert_eq!(KeyPress::parse("ctrl++").unwrap().code, KeyCode::Char('+'));
+        assert_eq!(KeyPress::parse("f5").unwrap().code, KeyCode::F(5));
+        assert!(KeyPress::parse("hyper+x").is_err());
+        assert!(KeyPress::parse("f0").is_err());
+        assert!(KeyPress::parse("f300").is_err());
+
+        let codes = NAMED_KEYS.iter().map(|(code, _)| *code);
+        let codes = codes
+            .chain(MEDIA_KEYS.map(KeyCode::Media))
+            .chain(MODIFIER_KEYS.map(KeyCode::Modifier))
+            .chain([' ', '+', 'a', 'A', '-'].map(KeyCode::Char))
+            .chain([KeyCode::F(1), KeyCode::F(255)]);
+        for code in codes {
+            let key = KeyPress::new(code, KeyModifiers::CONTROL | KeyModifiers::ALT);
+            assert_eq!(KeyPress::parse(&key.to_string()), Ok(key), "{}", key);
+        }
+
+        let seq = KeySequence::parse("ctrl+x  ctrl+s").unwrap();
+        assert_eq!(seq.0.len(), 2);
+        assert_eq!(seq.to_string(), "ctrl+x ctrl+s");
+        assert!(KeySequence::parse(" ").is_err());
+    }
+
+    #[test]
+    fn test_keymap_chords() {
+        with_tracking_scope(|| {
+            let actions = create_signal(vec![]);
+            let typed = create_signal(0);
+            use_keymap([
+                ("ctrl+s", Action::Save),
+                ("g g", Action::Top),
+                ("ctrl+x ctrl+s", Action::Save),
+                ("j", Action::Down),
+            ]);
+            let actions_c = actions.clone();
+            on(move |action: &Action| actions_c.update(|a| a.push(*action)));
+            on(move |_: &KeyEvent| typed.update(|t| *t += 1));
+
+            press("ctrl+s");
+            press("g");
+            assert_eq!(actions.get(), vec![Action::Save]);
+            press("g");
+            press("ctrl+x");
+            press("ctrl+s");
+            assert_eq!(actions.get(), vec![Action::Save, Action::Top, Action::Save]);
+            press("g");
+            press("j");
+            assert_eq!(actions.get().last(), Some(&Action::Down), "Restarts chord");
+            press("s");
+            assert_eq!(typed.get(), 1, "Unbound keys keep propagating");
+
+            press("g");
+            send(KeyCode::Char('g'));
+            assert_eq!(actions.get().len(), 4, "Key codes do not advance chords");
+        })
+    }
+
+    #[test]
+    fn test_keymap_modes() {
+        with_tracking_scope(|| {
+            let actions = create_signal(vec![]);
+            let mode = provide_key_mode("normal");
+            use_keymap_in("normal", [("i", Action::Insert), ("j", Action::Down)]);
+            use_keymap_in("insert", [("esc", Action::Top)]);
+            let actions_c = actions.clone();
+            on(move |action: &Action| actions_c.update(|a| a.push(*action)));
+
+            press("j");
+            press("esc");
+            assert_eq!(actions.get(), vec![Action::Down]);
+            mode.set("insert");
+            press("j");
+            press("esc");
+            assert_eq!(actions.get(), vec![Action::Down, Action::Top]);
+        })
+    }
+
+    #[test]
+    fn test_keymap_modes_default_without_provider() {
+        with_tracking_scope(|| {
+            let actions = create_signal(vec![]);
+            use_keymap_in(DEFAULT_KEY_MODE, [("j", Action::Down)]);
+            use_keymap_in("insert", [("esc", Action::Top)]);
+            let actions_c = actions.clone();
+            on(move |action: &Action| actions_c.update(|a| a.push(*action)));
+
+            press("j");
+            press("esc");
+            assert_eq!(actions.get(), vec![Action::Down]);
+        })
+    }
+
+    #[test]
+    fn test_binding_from_runtime_spec() {
+        let binding = Binding::new("ctrl+x ctrl+s", Action::Save).unwrap();
+        assert_eq!(binding.keys.to_string(), "ctrl+x ctrl+s");
+        let error = Binding::new("hyper+x", Action::Save).err().unwrap();
+        assert_eq!(error.spec, "hyper+x");
+    }
+
+    #[test]
+    fn test_help_overlay_lists_focused_chain() {
+        use crate::{focus::*, ratatui::*};
+        use ratatui::{buffer::Buffer, layout::Rect, widgets::WidgetRef};
+
+        fn list(name: &'static str) -> RatView {
+            use_focus();
+            use_keymap([("j", Action::Down, "Next item")]).named(name);
+            widget_ref(|| "")
+        }
+
+        with_tracking_scope(|| {
+            let scope = create_focus_scope();
+            use_keymap([("ctrl+s", Action::Save, "Save")]).named("App");
+            use_keymap_in("insert", [("esc", Action::Top, "Leave insert")]);
+            component(|| list("First"));
+            component(|| list("Second"));
+
+            let help = help_overlay();
+            let area = Rect::new(0, 0, 30, 8);
+            let text = || {
+                let mut buf = Buffer::empty(area);
+                help.render_ref(area, &mut buf);
+                (0..area.height)
+                    .map(|y| {
+                        let cells = (0..area.width).map(|x| buf.get(x, y).symbol().to_string());
+                        cells.collect::<String>().trim_end().to_string()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(
+                text(),
+                vec![
+                    "┌Help────────────────────────┐",
+                    "│ First                      │",
+                    "│ j       Next item          │",
+                    "│                            │",
+                    "│ App                        │",
+                    "│ ctrl+s  Save               │",
+                    "│                            │",
+                    "└────────────────────────────┘",
+                ]
+            );
+
+            scope.focus_next();
+            assert!(help.is_dirty(), "Focus change re-renders the overlay");
+            assert_eq!(text()[1], "│ Second                     │");
+            assert!(!help.is_dirty());
+        })
+    }
+
+    #[test]
+    fn test_ambiguous_chord_resolves_on_timeout() {
+        let mut state = KeymapState {
+            id: Scope::default(),
+            mode: None,
+            bindings: vec![("g", Action::Down).into(), ("g g", Action::Top).into()],
+            pending: vec![],
+            presses: 0,
+            last: Instant::now(),
+            timeout: Duration::ZERO,
+        };
+        let g = KeyPress::parse("g").unwrap();
+        assert_eq!(state.press(g), (vec![], true));
+        assert_eq!(state.expire(0), None, "Stale timeout");
+        assert_eq!(state.expire(1), Some(Action::Down));
+
+        state.press(g);
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(state.press(g), (vec![Action::Down], true));
+    }
+}
@@ -1,12 +1,26 @@
 #![feature(min_specialization)]
 
+pub mod action;
+pub mod app;
 pub mod context;
 pub mod environment;
+pub mod events;
+pub mod focus;
+pub mod keymap;
 pub mod macros;
+pub mod mouse;
 pub mod nodes;
 pub mod prelude;
 pub mod ratatui;
 pub mod recievers;
+pub mod record;
+pub mod resource;
 pub mod runtime;
+pub mod scheduler;
 pub mod signal;
+pub mod stats;
+pub mod stream;
 pub mod tasks;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timers;
@@ -36,3 +36,15 @@ macro_rules! reactive {
     }};
 }
 pub use reactive;
+
+/// Declares a [`ContextKey`](crate::context::ContextKey) constant with a marker type of its own
+#[macro_export]
+macro_rules! context_key {
+    ($vis:vis $name:ident: $ty:ty) => {
+        $vis const $name: $crate::context::ContextKey<$ty> = {
+            struct Marker;
+            $crate::context::ContextKey::new::<Marker>(stringify!($name))
+        };
+    };
+}
+pub use context_key;
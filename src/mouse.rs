@@ -0,0 +1,201 @@
+use std::{any::Any, cell::RefCell, rc::Rc};
+
+use crossterm::event::{MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use slotmap::SecondaryMap;
+
+use crate::{environment::*, nodes::Scope, recievers::IntoPropagation};
+
+#[derive(Default)]
+struct AreasState {
+    /// Last rendered area of each view with the order it was drawn in
+    rendered: SecondaryMap<Scope, (Rect, u64)>,
+    next_z: u64,
+    /// Areas drawn before this belong to a previous frame
+    frame_start: u64,
+    /// Target of the button press being dragged
+    capture: Option<(Scope, Rect)>,
+}
+
+/// Where each view was last rendered, used to find the view under the mouse
+#[derive(Default, Clone)]
+pub struct Areas(Rc<RefCell<AreasState>>);
+
+impl Areas {
+    pub(crate) fn record(&self, scope: Scope, area: Rect) {
+        let mut state = self.0.borrow_mut();
+        let z = state.next_z;
+        state.next_z += 1;
+        state.rendered.insert(scope, (area, z));
+    }
+
+    /// Forgets the areas drawn before the frame
+    pub fn begin_frame(&self) {
+        let mut state = self.0.borrow_mut();
+        state.frame_start = state.next_z;
+    }
+
+    pub fn get(&self, scope: Scope) -> Option<Rect> {
+        let state = self.0.borrow();
+        let (area, z) = state.rendered.get(scope)?;
+        (*z >= state.frame_start).then_some(*area)
+    }
+
+    /// The view drawn last in the current frame that contains the position
+    pub fn hit_test(&self, column: u16, row: u16) -> Option<(Scope, Rect)> {
+        let state = self.0.borrow();
+        let position = Rect::new(column, row, 1, 1);
+        let hits = state
+            .rendered
+            .iter()
+            .filter(|(_, (area, z))| *z >= state.frame_start && area.intersects(position));
+        let (scope, (area, _)) = hits.max_by_key(|(_, (_, z))| *z)?;
+        Some((scope, *area))
+    }
+
+    /// Finds the view the event is for and updates the drag capture
+    pub(crate) fn target(&self, event: &MouseEvent) -> Option<(Scope, Rect)> {
+        let hit = self.hit_test(event.column, event.row);
+        let mut state = self.0.borrow_mut();
+        let target = match event.kind {
+            MouseEventKind::Drag(_) | MouseEventKind::Up(_) => state.capture.or(hit),
+            _ => hit,
+        };
+        match event.kind {
+            MouseEventKind::Down(_) => state.capture = target,
+            MouseEventKind::Up(_) => state.capture = None,
+            _ => {}
+        }
+        target
+    }
+
+    pub(crate) fn dispose(&self, scope: Scope) {
+        let mut state = self.0.borrow_mut();
+        state.rendered.remove(scope);
+        if state.capture.map(|(s, _)| s) == Some(scope) {
+            state.capture = None;
+        }
+    }
+}
+
+/// Mouse event delivered to the view under the cursor and its ancestors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub event: MouseEvent,
+    /// Area of the view the event was delivered to
+    pub area: Rect,
+}
+
+impl Mouse {
+    /// Position relative to the top left corner of the area
+    pub fn position(&self) -> (u16, u16) {
+        (
+            self.event.column.saturating_sub(self.area.x),
+            self.event.row.saturating_sub(self.area.y),
+        )
+    }
+}
+
+/// Called for mouse events over the views rendered under the current scope.
+/// While a button is held every drag and the release go to the view that was pressed
+pub fn on_mouse<R: IntoPropagation>(f: impl Fn(&Mouse) -> R + 'static) {
+    on(f)
+}
+
+/// Call before drawing a frame so views that are no longer drawn stop receiving mouse events
+pub fn begin_frame() {
+    with_runtime(|r| r.areas.begin_frame())
+}
+
+/// Delivers a terminal mouse event to the topmost view under the cursor and its ancestors
+pub fn send_mouse(event: MouseEvent) {
+    if let Some((scope, area)) = with_runtime(|r| r.areas.target(&event)) {
+        let message = Box::new(Mouse { event, area }) as Box<dyn Any>;
+        with_runtime(|r| r.emit(scope, &message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ratatui::*, recievers::Propagation, signal::*};
+    use crossterm::event::{KeyModifiers, MouseButton};
+    use ratatui::{buffer::Buffer, widgets::WidgetRef};
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn clickable(name: &'static str, clicks: Signal<Vec<(&'static str, u16, u16)>>) -> RatView {
+        let clicks = clicks.clone();
+        on_mouse(move |m: &Mouse| {
+            let (x, y) = m.position();
+            clicks.update(|c| c.push((name, x, y)));
+            Propagation::Stop
+        });
+        widget_ref(|| "")
+    }
+
+    #[test]
+    fn test_hit_test_topmost_view() {
+        with_tracking_scope(|| {
+            let clicks = create_signal(vec![]);
+            let (clicks_a, clicks_b) = (clicks.clone(), clicks.clone());
+            let base = component(move || clickable("base", clicks_a.clone()));
+            let overlay = component(move || clickable("overlay", clicks_b.clone()));
+            let show_overlay = create_signal(true);
+            let app = render(move |area, buf| {
+                base.render_ref(area, buf);
+                if show_overlay.get() {
+                    overlay.render_ref(Rect::new(2, 1, 4, 2), buf);
+                }
+            });
+            let area = Rect::new(0, 0, 10, 5);
+            app.render_ref(area, &mut Buffer::empty(area));
+
+            send_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 3, 2));
+            send_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 8, 4));
+            assert_eq!(clicks.get(), vec![("overlay", 1, 1), ("base", 8, 4)]);
+            assert_eq!(overlay.area(), Some(Rect::new(2, 1, 4, 2)));
+
+            show_overlay.set(false);
+            begin_frame();
+            app.render_ref(area, &mut Buffer::empty(area));
+            send_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 3, 2));
+            assert_eq!(clicks.get().last(), Some(&("base", 3, 2)));
+            assert_eq!(overlay.area(), None);
+        })
+    }
+
+    #[test]
+    fn test_drag_captured_by_pressed_view() {
+        with_tracking_scope(|| {
+            let events = create_signal(vec![]);
+            let (events_a, events_b) = (events.clone(), events.clone());
+            let left = component(move || clickable("left", events_a.clone()));
+            let right = component(move || clickable("right", events_b.clone()));
+            let app = render(move |_, buf| {
+                left.render_ref(Rect::new(0, 0, 5, 1), buf);
+                right.render_ref(Rect::new(5, 0, 5, 1), buf);
+            });
+            let area = Rect::new(0, 0, 10, 1);
+            app.render_ref(area, &mut Buffer::empty(area));
+
+            send_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 1, 0));
+            send_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 7, 0));
+            send_mouse(mouse(MouseEventKind::Up(MouseButton::Left), 8, 0));
+            send_mouse(mouse(MouseEventKind::Moved, 8, 0));
+            let names = events
+                .get()
+                .into_iter()
+                .map(|(n, ..)| n)
+                .collect::<Vec<_>>();
+            assert_eq!(names, vec!["left", "left", "left", "right"]);
+        })
+    }
+}
@@ -25,6 +25,8 @@ pub struct ReactiveNode {
     pub(crate) parent: Option<Scope>,
     /// Nodes who depend on the value from this node
     pub(crate) dependants: HashSet<Scope>,
+    /// Child nodes in the order they were created
+    pub(crate) children: Vec<Scope>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -45,7 +47,9 @@ impl ReactiveNodes {
         node.parent = Some(scope);
         node.callback = cb;
         node.value = value;
-        self.insert(node)
+        let id = self.insert(node);
+        self.with_node(scope, |n| n.children.push(id));
+        id
     }
 
     pub(crate) fn with_node<R>(
@@ -57,10 +61,8 @@ impl ReactiveNodes {
     }
 
     pub(crate) fn get_node_children(&self, scope: Scope) -> Vec<Scope> {
-        let nodes = self.0.borrow();
-        let children = nodes.iter().filter(|x| x.1.parent == Some(scope));
-        let children = children.map(|x| x.0);
-        children.collect()
+        self.with_node(scope, |n| n.children.clone())
+            .unwrap_or_default()
     }
 
     pub(crate) fn get_node_children_recursive(&self, scope: Scope) -> Vec<Scope> {
@@ -72,6 +74,20 @@ impl ReactiveNodes {
         all
     }
 
+    /// Descendants depth first with parents before their children
+    pub(crate) fn get_node_descendants(&self, scope: Scope) -> Vec<Scope> {
+        let mut all = vec![];
+        for child in self.get_node_children(scope) {
+            all.push(child);
+            all.extend(self.get_node_descendants(child));
+        }
+        all
+    }
+
+    pub(crate) fn contains(&self, id: Scope) -> bool {
+        self.0.borrow().contains_key(id)
+    }
+
     pub(crate) fn get_parent(&self, id: Scope) -> Option<Scope> {
         self.0.borrow().get(id).map(|n| n.parent).flatten()
     }
@@ -121,7 +137,10 @@ impl ReactiveNodes {
 
     pub(crate) fn dispose(&self, id: Scope) {
         self.remove_scope_from_dependants(id);
-        self.0.borrow_mut().remove(id);
+        let parent = self.0.borrow_mut().remove(id).and_then(|n| n.parent);
+        if let Some(parent) = parent {
+            self.with_node(parent, |n| n.children.retain(|c| *c != id));
+        }
     }
 
     pub(crate) fn with_value<T: 'static, R>(
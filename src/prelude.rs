@@ -1 +1,5 @@
-pub use crate::{environment::*, macros::*, ratatui::*, signal::*, tasks::*};
+pub use crate::{
+    action::*, app::*, context::ContextKey, environment::*, events::*, focus::*, keymap::*,
+    macros::*, mouse::*, ratatui::*, recievers::Propagation, resource::*, signal::*, stream::*,
+    tasks::*, timers::*,
+};
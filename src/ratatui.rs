@@ -8,7 +8,7 @@ use crate::{
 };
 
 // Child
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RatView(Scope);
 impl IntoScope for RatView {
     fn into_scope(&self) -> Scope {
@@ -16,13 +16,31 @@ impl IntoScope for RatView {
     }
 }
 
+impl RatView {
+    /// Sends a message to the view's scope and its descendants
+    pub fn send<T: Any + 'static>(&self, message: T) {
+        send_to(*self, message)
+    }
+
+    /// Whether a signal the view depends on changed since it was last rendered
+    pub fn is_dirty(&self) -> bool {
+        with_runtime(|r| r.scheduler.is_dirty(self.0))
+    }
+
+    /// Area the view was drawn to in the latest frame
+    pub fn area(&self) -> Option<ratatui::prelude::Rect> {
+        with_runtime(|r| r.areas.get(self.0))
+    }
+}
+
 // WidgetRef Wrapper type
 pub struct WidgetNode(Box<dyn Fn(ratatui::prelude::Rect, &mut ratatui::prelude::Buffer)>);
 
 impl WidgetRef for RatView {
     fn render_ref(&self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        with_runtime(|r| r.areas.record(self.0, area));
         let signal = ReadSignal::<WidgetNode>(self.into_scope(), PhantomData);
-        signal.with_untracked(|v| v.0(area, buf));
+        with_runtime(|r| r.track_render(self.0, || signal.with_untracked(|v| v.0(area, buf))));
     }
 }
 
@@ -30,12 +48,14 @@ pub fn render(
     f: impl Fn(ratatui::prelude::Rect, &mut ratatui::prelude::Buffer) + 'static,
 ) -> RatView {
     let id = with_runtime(|s| s.create_value_node(Box::new(WidgetNode(Box::new(f)))));
+    with_runtime(|s| s.scheduler.add_view(id));
     RatView(id)
 }
 
 pub fn widget<V: Widget + Any>(f: impl Fn() -> V + 'static) -> RatView {
     let node = Box::new(WidgetNode(Box::new(move |area, buf| f().render(area, buf))));
     let id = with_runtime(|r| r.create_value_node(node));
+    with_runtime(|s| s.scheduler.add_view(id));
     RatView(id)
 }
 
@@ -44,16 +64,28 @@ pub fn widget_ref<V: WidgetRef + Any>(f: impl Fn() -> V + 'static) -> RatView {
         let w = f();
         WidgetNode(Box::new(move |area, buf| w.render_ref(area, buf)))
     });
+    with_runtime(|s| s.scheduler.add_view(memo.0));
     RatView(memo.0)
 }
 
+/// Views that changed since they were last rendered
+pub fn dirty_views() -> Vec<RatView> {
+    with_runtime(|r| r.scheduler.dirty_views().into_iter().map(RatView).collect())
+}
+
+/// Creates the view in its own scope so the recievers, contexts and tasks
+/// created by f belong to the returned view
+pub fn component(f: impl Fn() -> RatView + 'static) -> RatView {
+    widget_ref(f)
+}
+
 impl<T> From<ReadSignal<T>> for RatView
 where
     T: WidgetRef + 'static,
 {
     fn from(value: ReadSignal<T>) -> Self {
         render(move |area, buf| {
-            value.with_untracked(|x| x.render_ref(area, buf));
+            value.with(|x| x.render_ref(area, buf));
         })
     }
 }
@@ -64,7 +96,7 @@ where
 {
     fn from(value: Signal<T>) -> Self {
         render(move |area, buf| {
-            value.with_untracked(|x| x.render_ref(area, buf));
+            value.with(|x| x.render_ref(area, buf));
         })
     }
 }
@@ -79,11 +111,13 @@ where
         let widget = f();
         let state = state.clone();
         WidgetNode(Box::new(move |area, buf| {
+            with_runtime(|r| r.track_dependant(state.into_scope()));
             state
                 .clone()
                 .update_silent(|state| StatefulWidgetRef::render_ref(&widget, area, buf, state))
         }))
     });
+    with_runtime(|s| s.scheduler.add_view(memo.0));
     RatView(memo.0)
 }
 
@@ -8,60 +8,152 @@ use slotmap::SecondaryMap;
 
 use crate::nodes::Scope;
 
-pub struct Reciever(Box<dyn Fn(&Box<dyn Any>)>);
+/// Returned from a reciever to decide whether the message reaches the remaining recievers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Propagation {
+    Continue,
+    Stop,
+}
+
+pub trait IntoPropagation {
+    fn into_propagation(self) -> Propagation;
+}
+
+impl IntoPropagation for () {
+    fn into_propagation(self) -> Propagation {
+        Propagation::Continue
+    }
+}
+
+impl IntoPropagation for Propagation {
+    fn into_propagation(self) -> Propagation {
+        self
+    }
+}
+
+/// Called with every message sent to the scope of the reciever
+type RecieverFn = dyn Fn(&Box<dyn Any>) -> Propagation;
+
+pub struct Reciever {
+    alive: Cell<bool>,
+    /// Set while the reciever is called so a message it sends does not reach it again
+    running: Cell<bool>,
+    f: Box<RecieverFn>,
+}
 impl std::fmt::Debug for Reciever {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", type_name::<Reciever>())
     }
 }
 
+/// Clears the running flag of a reciever when its call returns or panics
+struct Running<'a>(&'a Cell<bool>);
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+type Handlers = Rc<RefCell<SecondaryMap<Scope, Vec<Rc<Reciever>>>>>;
+
 #[derive(Default, Debug, Clone)]
 pub struct Recievers {
-    handlers: Rc<RefCell<SecondaryMap<Scope, Vec<Reciever>>>>,
-    borrowed: Rc<Cell<Option<Scope>>>,
+    handlers: Handlers,
+    /// Recievers that see the messages sent or emitted from their scope or its descendants
+    watchers: Handlers,
 }
 
 impl Recievers {
-    pub fn create_reciever<T: 'static>(&self, scope: Scope, f: impl Fn(&T) + 'static) {
-        let borrowed = self.borrowed.clone();
-        let reciever = Reciever(Box::new(move |v| {
-            if borrowed.get() != Some(scope) {
-                return;
-            }
-            match v.downcast_ref::<T>() {
-                Some(v) => f(v),
-                None => {}
-            }
-        }));
-        let mut map = self.handlers.borrow_mut();
-        match map.get_mut(scope) {
-            Some(v) => v.push(reciever),
-            None => {
-                map.insert(scope, vec![reciever]);
-            }
-        };
+    pub fn create_reciever<T: 'static>(
+        &self,
+        scope: Scope,
+        f: impl Fn(&T) -> Propagation + 'static,
+    ) {
+        add_reciever(&self.handlers, scope, f);
     }
 
-    pub fn send(&self, scope: Scope, value: &Box<dyn Any>) {
-        let recievers = self.handlers.borrow_mut().remove(scope);
-        if let Some(recievers) = recievers {
-            let previous = self.borrowed.replace(Some(scope));
-            for r in &recievers {
-                r.0(value)
-            }
-            if self.borrowed.get() == Some(scope) {
-                self.handlers.borrow_mut().insert(scope, recievers);
-            }
-            self.borrowed.replace(previous);
+    pub fn create_watcher<T: 'static>(&self, scope: Scope, f: impl Fn(&T) + 'static) {
+        add_reciever(&self.watchers, scope, move |v| {
+            f(v);
+            Propagation::Continue
+        });
+    }
+
+    /// Calls the watchers of each scope, a message sent from a scope is passed the scope and
+    /// its ancestors. Watchers cannot stop propagation
+    pub fn notify(&self, scopes: &[Scope], value: &Box<dyn Any>) {
+        for scope in scopes {
+            let watchers = self.watchers.borrow().get(*scope).cloned();
+            call(watchers.iter().flatten(), value);
         }
     }
 
+    /// Calls the recievers of scope in the order they were registered until one stops propagation.
+    /// Recievers may send messages to any scope, including their own, while being called. A
+    /// reciever is skipped while it is already being called, so one that sends the message
+    /// type it handles does not recurse. Recievers disposed during the send are not called
+    pub fn send(&self, scope: Scope, value: &Box<dyn Any>) -> Propagation {
+        let recievers = self.handlers.borrow().get(scope).cloned();
+        call(recievers.iter().flatten(), value)
+    }
+
+    pub fn len(&self) -> usize {
+        let count = |h: &Handlers| h.borrow().values().map(|r| r.len()).sum::<usize>();
+        count(&self.handlers) + count(&self.watchers)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn dispose(&self, scope: Scope) {
-        match Some(scope) == self.borrowed.get() {
-            true => self.borrowed.set(None),
-            false => {
-                self.handlers.borrow_mut().remove(scope);
+        for handlers in [&self.handlers, &self.watchers] {
+            let recievers = handlers.borrow_mut().remove(scope);
+            for r in recievers.unwrap_or_default() {
+                r.alive.set(false);
             }
         }
     }
 }
+
+fn add_reciever<T: 'static>(
+    handlers: &Handlers,
+    scope: Scope,
+    f: impl Fn(&T) -> Propagation + 'static,
+) {
+    let reciever = Rc::new(Reciever {
+        alive: Cell::new(true),
+        running: Cell::new(false),
+        f: Box::new(move |v| match v.downcast_ref::<T>() {
+            Some(v) => f(v),
+            None => Propagation::Continue,
+        }),
+    });
+    let mut map = handlers.borrow_mut();
+    match map.get_mut(scope) {
+        Some(v) => v.push(reciever),
+        None => {
+            map.insert(scope, vec![reciever]);
+        }
+    };
+}
+
+/// Calls the live recievers that are not already running until one stops propagation
+fn call<'a>(
+    recievers: impl Iterator<Item = &'a Rc<Reciever>>,
+    value: &Box<dyn Any>,
+) -> Propagation {
+    for r in recievers {
+        if !r.alive.get() || r.running.replace(true) {
+            continue;
+        }
+        let running = Running(&r.running);
+        let propagation = (r.f)(value);
+        drop(running);
+        if propagation == Propagation::Stop {
+            return Propagation::Stop;
+        }
+    }
+    Propagation::Continue
+}
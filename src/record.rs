@@ -0,0 +1,356 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{
+    Event, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+use crate::keymap::KeyPress;
+
+const HEADER: &str = "# rizzup recording";
+
+/// An entry of a [`Recording`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recorded {
+    /// Terminal event passed to [`crate::events::send_event`]
+    Event(Event),
+    /// A message from an async task was delivered, by the type name of the message
+    Task(String),
+}
+
+/// Input of a session with the time since it started, saved as a header with the size of
+/// the screen followed by one line per entry such as `1250 key s ctrl` or
+/// `1300 task alloc::string::String`. Only the type of a task message is recorded, replaying
+/// runs the tasks again and delivers their messages at the recorded times
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// Width and height of the screen when the session started
+    pub size: (u16, u16),
+    pub entries: Vec<(Duration, Recorded)>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(std::fs::read_to_string(path)?.parse()?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl std::fmt::Display for Recording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {} {}", HEADER, self.size.0, self.size.1)?;
+        for (at, entry) in &self.entries {
+            if let Some(line) = format_entry(*at, entry) {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingParseError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for RecordingParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid recording line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for RecordingParseError {}
+
+impl FromStr for Recording {
+    type Err = RecordingParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut recording = Recording {
+            size: (80, 24),
+            entries: vec![],
+        };
+        for (i, line) in s.lines().enumerate() {
+            if let Some(size) = line.strip_prefix(HEADER) {
+                let mut size = size.split_whitespace().map(|n| n.parse::<u16>());
+                if let (Some(Ok(width)), Some(Ok(height))) = (size.next(), size.next()) {
+                    recording.size = (width, height);
+                }
+                continue;
+            }
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = parse_entry(line).map_err(|reason| RecordingParseError {
+                line: i + 1,
+                reason,
+            })?;
+            recording.entries.push(entry);
+        }
+        Ok(recording)
+    }
+}
+
+/// Appends each entry to a file as it happens so the recording survives a crash
+pub struct Recorder {
+    file: File,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>, size: (u16, u16)) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{} {} {}", HEADER, size.0, size.1)?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, entry: &Recorded) {
+        if let Some(line) = format_entry(self.start.elapsed(), entry) {
+            if let Err(e) = writeln!(self.file, "{}", line) {
+                tracing::warn!("Failed to record: {}", e);
+            }
+        }
+    }
+}
+
+/// None for events that are never sent such as key releases
+fn format_entry(at: Duration, entry: &Recorded) -> Option<String> {
+    let entry = match entry {
+        Recorded::Task(name) => format!("task {}", name),
+        Recorded::Event(Event::Key(key)) if key.kind != KeyEventKind::Press => return None,
+        Recorded::Event(Event::Key(key)) => {
+            // The code alone, so a shifted char keeps its modifier when replayed
+            let code = KeyPress {
+                code: key.code,
+                modifiers: KeyModifiers::NONE,
+            };
+            format!("key {} {}", code, format_modifiers(key.modifiers))
+        }
+        Recorded::Event(Event::Resize(width, height)) => format!("resize {} {}", width, height),
+        Recorded::Event(Event::Paste(text)) => format!("paste {}", escape(text)),
+        Recorded::Event(Event::FocusGained) => "focus gained".to_string(),
+        Recorded::Event(Event::FocusLost) => "focus lost".to_string(),
+        Recorded::Event(Event::Mouse(mouse)) => {
+            let kind = match mouse.kind {
+                MouseEventKind::Down(button) => format!("down-{}", button_name(button)),
+                MouseEventKind::Up(button) => format!("up-{}", button_name(button)),
+                MouseEventKind::Drag(button) => format!("drag-{}", button_name(button)),
+                MouseEventKind::Moved => "moved".to_string(),
+                MouseEventKind::ScrollDown => "scroll-down".to_string(),
+                MouseEventKind::ScrollUp => "scroll-up".to_string(),
+                MouseEventKind::ScrollLeft => "scroll-left".to_string(),
+                MouseEventKind::ScrollRight => "scroll-right".to_string(),
+            };
+            let modifiers = format_modifiers(mouse.modifiers);
+            format!(
+                "mouse {} {} {} {}",
+                kind, mouse.column, mouse.row, modifiers
+            )
+        }
+    };
+    Some(format!("{} {}", at.as_millis(), entry))
+}
+
+/// Key and mouse lines without modifiers, as in older recordings, are read as a key spec and
+/// no modifiers
+fn parse_entry(line: &str) -> Result<(Duration, Recorded), String> {
+    let (at, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let at = at.parse::<u64>().map_err(|_| "invalid time")?;
+    let (kind, args) = rest.split_once(' ').unwrap_or((rest, ""));
+    let number = |s: Option<&str>| {
+        s.and_then(|n| n.parse::<u16>().ok())
+            .ok_or("invalid number")
+    };
+    let event = match kind {
+        "task" => return Ok((Duration::from_millis(at), Recorded::Task(args.to_string()))),
+        "key" => {
+            let (spec, modifiers) = args.split_once(' ').unwrap_or((args, ""));
+            let key = KeyPress::parse(spec).map_err(|e| e.to_string())?;
+            let modifiers = match modifiers {
+                "" => key.modifiers,
+                modifiers => parse_modifiers(modifiers)?,
+            };
+            Event::Key(KeyEvent::new(key.code, modifiers))
+        }
+        "resize" => {
+            let mut size = args.split(' ');
+            Event::Resize(number(size.next())?, number(size.next())?)
+        }
+        "paste" => Event::Paste(unescape(args)),
+        "focus" => match args {
+            "gained" => Event::FocusGained,
+            "lost" => Event::FocusLost,
+            _ => return Err("invalid focus".into()),
+        },
+        "mouse" => {
+            let mut args = args.split(' ');
+            let kind = args.next().unwrap_or_default();
+            let button = |name: &str| match name {
+                "left" => Ok(MouseButton::Left),
+                "right" => Ok(MouseButton::Right),
+                "middle" => Ok(MouseButton::Middle),
+                _ => Err("invalid mouse button"),
+            };
+            let kind = match kind.split_once('-') {
+                Some(("down", b)) => MouseEventKind::Down(button(b)?),
+                Some(("up", b)) => MouseEventKind::Up(button(b)?),
+                Some(("drag", b)) => MouseEventKind::Drag(button(b)?),
+                Some(("scroll", "down")) => MouseEventKind::ScrollDown,
+                Some(("scroll", "up")) => MouseEventKind::ScrollUp,
+                Some(("scroll", "left")) => MouseEventKind::ScrollLeft,
+                Some(("scroll", "right")) => MouseEventKind::ScrollRight,
+                None if kind == "moved" => MouseEventKind::Moved,
+                _ => return Err("invalid mouse event".into()),
+            };
+            Event::Mouse(MouseEvent {
+                kind,
+                column: number(args.next())?,
+                row: number(args.next())?,
+                modifiers: parse_modifiers(args.next().unwrap_or("none"))?,
+            })
+        }
+        _ => return Err(format!("unknown entry `{}`", kind)),
+    };
+    Ok((Duration::from_millis(at), Recorded::Event(event)))
+}
+
+const MODIFIERS: [(KeyModifiers, &str); 6] = [
+    (KeyModifiers::CONTROL, "ctrl"),
+    (KeyModifiers::ALT, "alt"),
+    (KeyModifiers::SHIFT, "shift"),
+    (KeyModifiers::SUPER, "super"),
+    (KeyModifiers::HYPER, "hyper"),
+    (KeyModifiers::META, "meta"),
+];
+
+/// Names joined by `+` like `ctrl+shift`, `none` without modifiers
+fn format_modifiers(modifiers: KeyModifiers) -> String {
+    let names = MODIFIERS
+        .iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+    match names.is_empty() {
+        true => "none".to_string(),
+        false => names.join("+"),
+    }
+}
+
+fn parse_modifiers(text: &str) -> Result<KeyModifiers, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    for name in text.split('+').filter(|n| *n != "none") {
+        match MODIFIERS.iter().find(|(_, n)| *n == name) {
+            Some((modifier, _)) => modifiers |= *modifier,
+            None => return Err(format!("unknown modifier `{}`", name)),
+        }
+    }
+    Ok(modifiers)
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, ch == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some(ch) => unescaped.push(ch),
+                None => unescaped.push('\\'),
+            },
+            (ch, false) => unescaped.push(ch),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let ms = Duration::from_millis;
+        let key = |code, modifiers| Recorded::Event(Event::Key(KeyEvent::new(code, modifiers)));
+        let recording = Recording {
+            size: (80, 24),
+            entries: vec![
+                (ms(0), Recorded::Event(Event::Resize(100, 30))),
+                (ms(120), key(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+                (ms(130), key(KeyCode::Char('S'), KeyModifiers::SHIFT)),
+                (ms(140), key(KeyCode::BackTab, KeyModifiers::SHIFT)),
+                (ms(900), Recorded::Event(Event::Paste("a\\b\nc d".into()))),
+                (ms(950), Recorded::Event(Event::FocusLost)),
+                (
+                    ms(960),
+                    Recorded::Task("(i32, alloc::string::String)".into()),
+                ),
+                (
+                    ms(1000),
+                    Recorded::Event(Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::Down(MouseButton::Left),
+                        column: 3,
+                        row: 4,
+                        modifiers: KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                    })),
+                ),
+            ],
+        };
+        let text = recording.to_string();
+        assert_eq!(
+            text.lines().nth(2),
+            Some("120 key s ctrl"),
+            "recording:\n{}",
+            text
+        );
+        assert_eq!(text.parse::<Recording>(), Ok(recording));
+
+        let old = "# rizzup recording 80 24\n5 key ctrl+s\n6 mouse moved 1 2"
+            .parse::<Recording>()
+            .unwrap();
+        assert_eq!(
+            old.entries[0].1,
+            key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        );
+
+        let error = "# rizzup recording 80 24\n10 jump"
+            .parse::<Recording>()
+            .unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+}
@@ -0,0 +1,159 @@
+use std::{cell::Cell, fmt::Display, marker::PhantomData, rc::Rc};
+
+use futures::Future;
+
+use crate::{
+    environment::*,
+    nodes::{IntoScope, Scope},
+    signal::*,
+    tasks::TaskRunner,
+};
+
+/// Result of a fetch sent back to the scope that created the resource
+struct Fetched<T> {
+    resource: Scope,
+    version: u64,
+    result: Result<T, String>,
+}
+
+/// Data loaded asynchronously from a source signal, see [`create_resource`]
+#[derive(Clone)]
+pub struct Resource<S, T> {
+    value: Signal<Option<T>>,
+    loading: Signal<bool>,
+    error: Signal<Option<String>>,
+    source: PhantomData<S>,
+}
+
+impl<S, T: Clone + 'static> Resource<S, T> {
+    /// The last value that loaded, kept while a new one is fetched
+    pub fn get(&self) -> Option<T> {
+        self.value.get()
+    }
+
+    pub fn loading(&self) -> bool {
+        self.loading.get()
+    }
+
+    /// Error of the last fetch, cleared when a fetch succeeds
+    pub fn error(&self) -> Option<String> {
+        self.error.get()
+    }
+}
+
+/// Runs fetcher with the value of source on the [`TaskRunner`] in context and again whenever
+/// source changes. A fetch still running when source changes is cancelled and its result
+/// is discarded
+pub fn create_resource<S, T, E, Fu>(
+    source: impl SignalGet<S> + 'static,
+    fetcher: impl Fn(S) -> Fu + 'static,
+) -> Resource<S, T>
+where
+    S: Clone + 'static,
+    T: Clone + Send + 'static,
+    E: Display,
+    Fu: Future<Output = Result<T, E>> + Send + 'static,
+{
+    let value = create_signal(None);
+    let loading = create_signal(true);
+    let error = create_signal(None);
+    let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let version = Rc::new(Cell::new(0));
+
+    let version_c = version.clone();
+    let fetch = create_memo(move || {
+        let data = source.get();
+        let id = with_runtime(|r| r.get_current_scope());
+        version_c.set(version_c.get() + 1);
+        let version = version_c.get();
+        loading.set(true);
+
+        let tx = tasks.transmitter(owner);
+        let fut = fetcher(data);
+        tasks.spawn_cancellable(id, async move {
+            let result = fut.await.map_err(|e| e.to_string());
+            tx.send(Fetched {
+                resource: id,
+                version,
+                result,
+            });
+        });
+        let tasks = tasks.clone();
+        on_cleanup(move || tasks.cancel(id));
+    });
+    let id = fetch.into_scope();
+
+    let value_c = value.clone();
+    let error_c = error.clone();
+    on(move |fetched: &Fetched<T>| {
+        if fetched.resource != id || fetched.version != version.get() {
+            return;
+        }
+        match &fetched.result {
+            Ok(v) => {
+                value_c.set(Some(v.clone()));
+                error_c.set(None);
+            }
+            Err(e) => error_c.set(Some(e.clone())),
+        }
+        loading.set(false);
+    });
+
+    Resource {
+        value,
+        loading,
+        error,
+        source: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ratatui::widget_ref, testing::TestApp};
+    use std::time::Duration;
+
+    #[test]
+    fn test_resource_refetches_and_discards_stale() {
+        let (mut app, (query, users)) = TestApp::mount(20, 1, || {
+            let query = create_signal(0);
+            let users = create_resource(query, |id: i32| async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                match id {
+                    3 => Err("not found"),
+                    id => Ok(format!("user {}", id)),
+                }
+            });
+            let values = (query, users.clone());
+            (
+                widget_ref(
+                    move || match (users.loading(), users.get(), users.error()) {
+                        (_, _, Some(e)) => e,
+                        (true, _, _) => "loading".to_string(),
+                        (false, v, _) => v.unwrap_or_default(),
+                    },
+                ),
+                values,
+            )
+        });
+        assert_eq!(app.to_string(), "loading\n");
+        app.advance(Duration::from_millis(100));
+        assert_eq!(app.to_string(), "user 0\n");
+
+        app.enter(|| query.set(1));
+        assert!(app.enter(|| users.loading()));
+        assert_eq!(app.enter(|| users.get()), Some("user 0".to_string()));
+        app.advance(Duration::from_millis(50));
+        app.enter(|| query.set(2));
+        app.advance(Duration::from_millis(60));
+        assert!(app.enter(|| users.loading()));
+        app.advance(Duration::from_millis(40));
+        assert_eq!(app.to_string(), "user 2\n");
+
+        app.enter(|| query.set(3));
+        app.advance(Duration::from_millis(100));
+        assert_eq!(app.to_string(), "not found\n");
+        assert_eq!(app.enter(|| users.get()), Some("user 2".to_string()));
+    }
+}
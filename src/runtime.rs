@@ -1,15 +1,21 @@
 use std::{
-    any::Any,
+    any::{Any, TypeId},
     cell::{Cell, RefCell},
     rc::Rc,
 };
 
+use ratatui::layout::Rect;
 use slotmap::SecondaryMap;
 
 use crate::{
     context::Contexts,
+    focus::Focus,
+    keymap::Keymaps,
+    mouse::Areas,
     nodes::{Callback, ReactiveNodes, Scope},
-    recievers::Recievers,
+    recievers::{Propagation, Recievers},
+    scheduler::Scheduler,
+    stats::{LeakCheck, LeakReport, RuntimeStats},
 };
 
 #[derive(Default, Clone)]
@@ -19,6 +25,13 @@ pub struct Runtime {
     pub cleanup: Rc<RefCell<SecondaryMap<Scope, Vec<Box<dyn FnOnce()>>>>>,
     pub context: Contexts,
     pub recievers: Recievers,
+    pub focus: Focus,
+    pub keymaps: Keymaps,
+    pub areas: Areas,
+    pub scheduler: Scheduler,
+    pub leak_check: Rc<RefCell<Option<LeakCheck>>>,
+    /// Last known size of the terminal, set by the app runner or test app
+    pub terminal_size: Rc<Cell<Rect>>,
 }
 
 impl Runtime {
@@ -42,6 +55,12 @@ impl Runtime {
         self.nodes.add_node(scope, None, Some(value))
     }
 
+    /// Creates a node without a value or callback under the current scope
+    pub fn create_scope_node(&self) -> Scope {
+        let scope = self.get_current_scope();
+        self.nodes.add_node(scope, None, None)
+    }
+
     pub fn update_dependants(&self, node: Scope) {
         self.recompute(node);
     }
@@ -75,12 +94,19 @@ impl Runtime {
             self.dispose_of_children(id);
             self.nodes.remove_scope_from_dependants(id);
             self.recievers.dispose(id);
+            self.context.dispose(id);
+            self.focus.dispose_routes(id);
 
             let new_value = match &previous_value {
                 Some(val) => self.with_tracking_scope(id, || callback.0(Some(val))),
                 None => self.with_tracking_scope(id, || callback.0(None)),
             };
-            return self.nodes.update(id, callback, new_value, previous_value);
+            if let Some(view) = new_value.as_ref().and(self.nearest_view(id)) {
+                self.scheduler.mark_dirty(view);
+            }
+            let deps = self.nodes.update(id, callback, new_value, previous_value);
+            self.sweep_focus(id);
+            return deps;
         }
         self.nodes.take_dependants(id)
     }
@@ -99,7 +125,8 @@ impl Runtime {
         let mut children = self.nodes.get_node_children_recursive(id);
         children.push(id);
         for child in children {
-            for cleanup in self.cleanup.borrow_mut().remove(child).unwrap_or_default() {
+            let cleanups = self.cleanup.borrow_mut().remove(child);
+            for cleanup in cleanups.unwrap_or_default() {
                 cleanup()
             }
         }
@@ -107,40 +134,156 @@ impl Runtime {
 
     pub fn dispose_of_children(&self, scope: Scope) {
         for child in self.nodes.get_node_children_recursive(scope) {
-            self.recievers.dispose(child);
-            self.nodes.dispose(child);
+            self.dispose_node(child);
+        }
+    }
+
+    fn dispose_node(&self, id: Scope) {
+        self.sweep_focus(id);
+        self.focus.dispose_routes(id);
+        self.recievers.dispose(id);
+        self.context.dispose(id);
+        self.areas.dispose(id);
+        self.scheduler.dispose(id);
+        self.nodes.dispose(id);
+    }
+
+    /// Moves focus away from scope if it was focusable and did not register again
+    fn sweep_focus(&self, id: Scope) {
+        if let Some(next) = self.focus.sweep(id) {
+            crate::focus::focus(next);
         }
     }
 
     pub fn cleanup_child_scope(&self, scope: Scope) {
         let children = self.nodes.get_node_children_recursive(scope);
         for child in &children {
-            for cleanup in self.cleanup.borrow_mut().remove(*child).unwrap_or_default() {
+            let cleanups = self.cleanup.borrow_mut().remove(*child);
+            for cleanup in cleanups.unwrap_or_default() {
                 cleanup()
             }
         }
         for child in children {
-            self.recievers.dispose(child);
-            self.nodes.dispose(child);
+            self.dispose_node(child);
+        }
+    }
+
+    /// Runs cleanups and removes the scope along with everything created under it
+    pub fn dispose(&self, scope: Scope) {
+        self.cleanup_child_scope(scope);
+        let cleanups = self.cleanup.borrow_mut().remove(scope);
+        for cleanup in cleanups.unwrap_or_default() {
+            cleanup()
+        }
+        self.dispose_node(scope);
+    }
+
+    fn nearest_view(&self, id: Scope) -> Option<Scope> {
+        let chain = self.scope_chain(id);
+        chain.into_iter().find(|s| self.scheduler.is_view(*s))
+    }
+
+    /// Runs the render of a view with the signals it reads tracked, a change to any of them
+    /// marks the view dirty. Dependencies from the previous render are dropped first
+    pub fn track_render<R>(&self, view: Scope, f: impl FnOnce() -> R) -> R {
+        let tracker = match self.scheduler.tracker(view) {
+            Some(tracker) if self.nodes.contains(tracker) => tracker,
+            _ => {
+                let scheduler = self.scheduler.clone();
+                let callback = Callback(Box::new(move |_| {
+                    scheduler.mark_dirty(view);
+                    None
+                }));
+                let tracker = self.nodes.add_node(view, Some(callback), None);
+                self.scheduler.set_tracker(view, tracker);
+                tracker
+            }
+        };
+        self.cleanup_child_scope(tracker);
+        self.nodes.remove_scope_from_dependants(tracker);
+        self.scheduler.mark_rendered(view);
+        self.with_tracking_scope(tracker, f)
+    }
+
+    /// The scope followed by each of its ancestors
+    pub fn scope_chain(&self, id: Scope) -> Vec<Scope> {
+        let mut chain = vec![id];
+        while let Some(parent) = self.nodes.get_parent(*chain.last().unwrap()) {
+            chain.push(parent);
         }
+        chain
     }
 
-    pub fn get_context<T: Clone + Any + 'static>(&self, id: Scope) -> Option<T> {
-        match self.context.use_context_from_scope(id) {
+    pub fn get_context<T: Any + 'static>(&self, id: Scope, key: Option<TypeId>) -> Option<Rc<T>> {
+        match self.context.use_context_from_scope(id, key) {
             Some(v) => Some(v),
             None => match self.nodes.get_parent(id) {
-                Some(id) => self.get_context(id),
+                Some(id) => self.get_context(id, key),
                 None => None,
             },
         }
     }
 
-    pub fn send(&self, scope: Scope, value: &Box<dyn Any>, deep: bool) {
-        self.recievers.send(scope, value);
+    /// Delivers to scope and, when deep, to its descendants depth first in the order
+    /// they were created. Stops at the first reciever returning [`Propagation::Stop`].
+    /// Messages routed to focus only reach the scopes from scope down to the focused scope
+    pub fn send(&self, scope: Scope, value: &Box<dyn Any>, deep: bool) -> Propagation {
+        self.recievers.notify(&self.scope_chain(scope), value);
+        if let Some(focused) = self.focus.focused() {
+            if deep && self.focus.is_routed((**value).type_id()) {
+                let mut chain = self.scope_chain(focused);
+                if let Some(i) = chain.iter().position(|s| *s == scope) {
+                    chain.truncate(i + 1);
+                    chain.reverse();
+                    return self.dispatch(chain, value);
+                }
+            }
+        }
+        let mut scopes = vec![scope];
         if deep {
-            for c in self.nodes.get_node_children_recursive(scope) {
-                self.recievers.send(c, value);
+            scopes.extend(self.nodes.get_node_descendants(scope));
+        }
+        self.dispatch(scopes, value)
+    }
+
+    /// Delivers to scope and then each of its ancestors
+    pub fn emit(&self, scope: Scope, value: &Box<dyn Any>) -> Propagation {
+        let chain = self.scope_chain(scope);
+        self.recievers.notify(&chain, value);
+        self.dispatch(chain, value)
+    }
+
+    fn dispatch(&self, scopes: Vec<Scope>, value: &Box<dyn Any>) -> Propagation {
+        for scope in scopes {
+            if self.recievers.send(scope, value) == Propagation::Stop {
+                return Propagation::Stop;
+            }
+        }
+        Propagation::Continue
+    }
+
+    pub fn stats(&self) -> RuntimeStats {
+        let mut stats = RuntimeStats::default();
+        for (_, node) in self.nodes.0.borrow().iter() {
+            match (&node.callback, &node.value) {
+                (Some(_), _) => stats.computed_nodes += 1,
+                (None, Some(_)) => stats.value_nodes += 1,
+                (None, None) => stats.scope_nodes += 1,
             }
         }
+        stats.recievers = self.recievers.len();
+        stats.cleanups = self.cleanup.borrow().values().map(|c| c.len()).sum();
+        stats.contexts = self.context.len();
+        stats
+    }
+
+    /// Records a frame for the leak check if it is enabled
+    pub fn check_leaks(&self) -> Option<LeakReport> {
+        let stats = self.stats();
+        let report = self.leak_check.borrow_mut().as_mut()?.frame(stats);
+        if let Some(report) = &report {
+            tracing::warn!("{}", report);
+        }
+        report
     }
 }
@@ -0,0 +1,214 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use slotmap::SecondaryMap;
+
+use crate::nodes::Scope;
+
+#[derive(Default, Clone, Copy)]
+struct ViewState {
+    /// Node depending on the signals read by the last render
+    tracker: Option<Scope>,
+    dirty: bool,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    redraw: bool,
+    views: SecondaryMap<Scope, ViewState>,
+}
+
+/// Tracks whether the views need to be drawn again
+#[derive(Default, Clone)]
+pub struct Scheduler(Rc<RefCell<SchedulerState>>);
+
+impl Scheduler {
+    pub(crate) fn add_view(&self, scope: Scope) {
+        let view = ViewState {
+            tracker: None,
+            dirty: true,
+        };
+        self.0.borrow_mut().views.insert(scope, view);
+    }
+
+    pub fn is_view(&self, scope: Scope) -> bool {
+        self.0.borrow().views.contains_key(scope)
+    }
+
+    pub(crate) fn tracker(&self, view: Scope) -> Option<Scope> {
+        self.0.borrow().views.get(view).and_then(|v| v.tracker)
+    }
+
+    pub(crate) fn set_tracker(&self, view: Scope, tracker: Scope) {
+        if let Some(v) = self.0.borrow_mut().views.get_mut(view) {
+            v.tracker = Some(tracker);
+        }
+    }
+
+    /// Marks the view as changed since it was last rendered and requests a redraw
+    pub fn mark_dirty(&self, view: Scope) {
+        let mut state = self.0.borrow_mut();
+        if let Some(v) = state.views.get_mut(view) {
+            v.dirty = true;
+            state.redraw = true;
+        }
+    }
+
+    pub(crate) fn mark_rendered(&self, view: Scope) {
+        if let Some(v) = self.0.borrow_mut().views.get_mut(view) {
+            v.dirty = false;
+        }
+    }
+
+    pub fn is_dirty(&self, view: Scope) -> bool {
+        self.0.borrow().views.get(view).is_some_and(|v| v.dirty)
+    }
+
+    /// Views that changed since they were last rendered
+    pub fn dirty_views(&self) -> Vec<Scope> {
+        let state = self.0.borrow();
+        let dirty = state.views.iter().filter(|(_, v)| v.dirty);
+        dirty.map(|(scope, _)| scope).collect()
+    }
+
+    pub fn request_redraw(&self) {
+        self.0.borrow_mut().redraw = true;
+    }
+
+    /// Whether a redraw was requested since the last call
+    pub fn take_redraw(&self) -> bool {
+        std::mem::take(&mut self.0.borrow_mut().redraw)
+    }
+
+    pub(crate) fn dispose(&self, scope: Scope) {
+        self.0.borrow_mut().views.remove(scope);
+    }
+}
+
+/// Spaces frames at least an interval apart
+#[derive(Debug, Clone, Copy)]
+pub struct FrameLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// No limit when fps is 0
+    pub fn new(fps: u32) -> Self {
+        let interval = match fps {
+            0 => Duration::ZERO,
+            fps => Duration::from_secs(1) / fps,
+        };
+        Self {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Time left until the next frame can be drawn
+    pub fn remaining(&self, now: Instant) -> Duration {
+        match self.last {
+            Some(last) => (last + self.interval).saturating_duration_since(now),
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn frame(&mut self, now: Instant) {
+        self.last = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{environment::*, ratatui::*, signal::*};
+    use ratatui::widgets::{Paragraph, WidgetRef};
+
+    #[test]
+    fn test_redraw_when_view_recomputes() {
+        with_tracking_scope(|| {
+            let count = create_signal(0);
+            let unrelated = create_signal(0);
+            let view = widget_ref(move || count.get().to_string());
+            render_views(&[view]);
+
+            unrelated.set(1);
+            assert!(!with_runtime(|r| r.scheduler.take_redraw()));
+            assert!(!view.is_dirty());
+
+            count.set(1);
+            assert!(with_runtime(|r| r.scheduler.take_redraw()));
+            assert!(!with_runtime(|r| r.scheduler.take_redraw()));
+            assert_eq!(dirty_views().len(), 1);
+        })
+    }
+
+    #[test]
+    fn test_render_closure_reads_tracked() {
+        with_tracking_scope(|| {
+            let (a, b) = (create_signal("a"), create_signal("b"));
+            let view_a = render(move |area, buf| a.get().render_ref(area, buf));
+            let view_b = render(move |area, buf| b.get().render_ref(area, buf));
+            let parent = render(move |area, buf| {
+                view_a.render_ref(area, buf);
+                view_b.render_ref(area, buf);
+            });
+            render_views(&[parent]);
+            assert!(dirty_views().is_empty());
+
+            b.set("c");
+            assert_eq!(dirty_views(), vec![view_b]);
+            assert!(with_runtime(|r| r.scheduler.take_redraw()));
+
+            render_views(&[parent]);
+            a.set("d");
+            assert!(view_a.is_dirty() && !view_b.is_dirty() && !parent.is_dirty());
+        })
+    }
+
+    #[test]
+    fn test_widget_reads_tracked() {
+        with_tracking_scope(|| {
+            let count = create_signal(0);
+            let view = widget(move || Paragraph::new(count.get().to_string()));
+            render_views(&[view]);
+            let nodes = runtime_stats().nodes();
+            render_views(&[view]);
+            assert_eq!(runtime_stats().nodes(), nodes);
+
+            count.set(1);
+            assert!(view.is_dirty());
+            assert!(with_runtime(|r| r.scheduler.take_redraw()));
+        })
+    }
+
+    fn render_views(views: &[RatView]) {
+        let area = ratatui::layout::Rect::new(0, 0, 10, 1);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        for view in views {
+            view.render_ref(area, &mut buf);
+        }
+        with_runtime(|r| r.scheduler.take_redraw());
+    }
+
+    #[test]
+    fn test_frame_limiter() {
+        let start = Instant::now();
+        let mut frames = FrameLimiter::new(10);
+        assert_eq!(frames.remaining(start), Duration::ZERO);
+        frames.frame(start);
+        assert_eq!(frames.remaining(start), Duration::from_millis(100));
+        assert_eq!(
+            frames.remaining(start + Duration::from_millis(60)),
+            Duration::from_millis(40)
+        );
+        assert_eq!(
+            frames.remaining(start + Duration::from_secs(1)),
+            Duration::ZERO
+        );
+        assert_eq!(FrameLimiter::new(0).remaining(start), Duration::ZERO);
+    }
+}
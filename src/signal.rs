@@ -72,9 +72,15 @@ macro_rules! impl_signal_get {
     };
 }
 
-#[derive(Clone, Copy)]
 pub struct ReadSignal<T>(pub Scope, pub PhantomData<T>);
 
+impl<T> Clone for ReadSignal<T> {
+    fn clone(&self) -> Self {
+        Self(self.0, PhantomData)
+    }
+}
+impl<T: Copy> Copy for ReadSignal<T> {}
+
 impl<T> IntoScope for ReadSignal<T> {
     fn into_scope(&self) -> Scope {
         self.0
@@ -83,9 +89,15 @@ impl<T> IntoScope for ReadSignal<T> {
 impl<T: 'static> SignalRead<T> for ReadSignal<T> {}
 impl_signal_get!(ReadSignal);
 
-#[derive(Clone, Copy)]
 pub struct WriteSignal<T>(pub Scope, pub PhantomData<T>);
 
+impl<T> Clone for WriteSignal<T> {
+    fn clone(&self) -> Self {
+        Self(self.0, PhantomData)
+    }
+}
+impl<T: Copy> Copy for WriteSignal<T> {}
+
 impl<T> IntoScope for WriteSignal<T> {
     fn into_scope(&self) -> Scope {
         self.0
@@ -94,8 +106,14 @@ impl<T> IntoScope for WriteSignal<T> {
 impl<T: 'static> SignalUpdate<T> for WriteSignal<T> {}
 impl<T: 'static> SignalSet<T> for WriteSignal<T> {}
 
-#[derive(Clone, Copy)]
 pub struct Signal<T>(pub Scope, pub PhantomData<T>);
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self(self.0, PhantomData)
+    }
+}
+impl<T: Copy> Copy for Signal<T> {}
 impl<T> IntoScope for Signal<T> {
     fn into_scope(&self) -> Scope {
         self.0
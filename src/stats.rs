@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+/// Snapshot of the number of entries held by the runtime
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeStats {
+    /// Nodes holding a value without a callback (signals, views)
+    pub value_nodes: usize,
+    /// Nodes with a callback (memos, selectors)
+    pub computed_nodes: usize,
+    /// Nodes without a value or callback (tracking scopes)
+    pub scope_nodes: usize,
+    /// Registered message recievers
+    pub recievers: usize,
+    /// Pending cleanup callbacks
+    pub cleanups: usize,
+    /// Provided context entries
+    pub contexts: usize,
+}
+
+impl RuntimeStats {
+    pub fn nodes(&self) -> usize {
+        self.value_nodes + self.computed_nodes + self.scope_nodes
+    }
+
+    fn counts(&self) -> [(&'static str, usize); 3] {
+        [
+            ("nodes", self.nodes()),
+            ("recievers", self.recievers),
+            ("cleanups", self.cleanups),
+        ]
+    }
+}
+
+/// Counts that grew on every frame of a leak check window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakReport {
+    pub frames: usize,
+    pub before: RuntimeStats,
+    pub after: RuntimeStats,
+    pub grown: Vec<&'static str>,
+}
+
+impl std::fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Possible leak over {} frames:", self.frames)?;
+        let before = self.before.counts();
+        let after = self.after.counts();
+        for ((name, from), (_, to)) in before.iter().zip(after.iter()) {
+            if self.grown.contains(name) {
+                write!(f, " {} {} -> {}", name, from, to)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the stats of the last frames and reports counts that grow on every frame
+#[derive(Debug, Clone)]
+pub struct LeakCheck {
+    frames: usize,
+    history: VecDeque<RuntimeStats>,
+}
+
+impl LeakCheck {
+    pub fn new(frames: usize) -> Self {
+        Self {
+            frames: frames.max(1),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn frame(&mut self, stats: RuntimeStats) -> Option<LeakReport> {
+        self.history.push_back(stats);
+        if self.history.len() > self.frames + 1 {
+            self.history.pop_front();
+        }
+        if self.history.len() <= self.frames {
+            return None;
+        }
+
+        let mut grown = vec![];
+        for (i, (name, _)) in stats.counts().iter().enumerate() {
+            let counts = self
+                .history
+                .iter()
+                .map(|s| s.counts()[i].1)
+                .collect::<Vec<_>>();
+            if counts.windows(2).all(|w| w[1] > w[0]) {
+                grown.push(*name);
+            }
+        }
+        if grown.is_empty() {
+            return None;
+        }
+        Some(LeakReport {
+            frames: self.frames,
+            before: self.history[0],
+            after: stats,
+            grown,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use std::{cell::Cell, marker::PhantomData, rc::Rc};
+
+use futures::{stream::BoxStream, Stream, StreamExt};
+use tokio::sync::watch;
+
+use crate::{
+    environment::*,
+    nodes::{IntoScope, Scope},
+    signal::*,
+    tasks::TaskRunner,
+};
+
+/// Item of a subscription sent back to the scope that created it, taken by the first
+/// handler
+struct StreamItem<I> {
+    stream: Scope,
+    subscription: u64,
+    item: Cell<Option<I>>,
+}
+
+/// Signal holding the latest item of the stream, see [`create_stream_fold`]
+pub fn create_stream_signal<T, St>(initial: T, factory: impl Fn() -> St + 'static) -> ReadSignal<T>
+where
+    T: Send + 'static,
+    St: Stream<Item = T> + Send + 'static,
+{
+    create_stream_fold(initial, factory, |value, item| *value = item)
+}
+
+/// Polls the stream returned by factory on the [`TaskRunner`] in context and folds each item
+/// into a signal. Signals read by factory are tracked, when they change the stream is
+/// dropped and factory is called again. The stream is dropped when the current scope is
+/// disposed
+pub fn create_stream_fold<T, I, St>(
+    initial: T,
+    factory: impl Fn() -> St + 'static,
+    fold: impl Fn(&mut T, I) + 'static,
+) -> ReadSignal<T>
+where
+    T: 'static,
+    I: Send + 'static,
+    St: Stream<Item = I> + Send + 'static,
+{
+    let value = create_signal(initial);
+    let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let subscription = Rc::new(Cell::new(0));
+
+    let subscription_c = subscription.clone();
+    let subscribe = create_memo(move || {
+        let stream = factory();
+        let id = with_runtime(|r| r.get_current_scope());
+        subscription_c.set(subscription_c.get() + 1);
+        let subscription = subscription_c.get();
+
+        let tx = tasks.transmitter(owner);
+        tasks.spawn_cancellable(id, async move {
+            let mut stream = Box::pin(stream);
+            while let Some(item) = stream.next().await {
+                tx.send(StreamItem {
+                    stream: id,
+                    subscription,
+                    item: Cell::new(Some(item)),
+                });
+            }
+        });
+        let tasks = tasks.clone();
+        on_cleanup(move || tasks.cancel(id));
+    });
+    let id = subscribe.into_scope();
+
+    let value_c = value.clone();
+    on(move |message: &StreamItem<I>| {
+        if message.stream != id || message.subscription != subscription.get() {
+            return;
+        }
+        if let Some(item) = message.item.take() {
+            value_c.update(|v| fold(v, item));
+        }
+    });
+    ReadSignal(value.0, PhantomData)
+}
+
+/// Observe a signal from async code
+pub trait SignalWatch<T: Clone + Send + Sync + 'static>: SignalGet<T> + Clone + 'static {
+    /// Receiver of the value of the signal, updated whenever it changes until the current
+    /// scope is disposed
+    fn watch(&self) -> watch::Receiver<T> {
+        let (tx, mut rx) = watch::channel(self.get_untracked());
+        let signal = self.clone();
+        create_memo(move || {
+            tx.send_replace(signal.get());
+        });
+        rx.mark_unchanged();
+        rx
+    }
+
+    /// Yields the current value and then a clone after every change, values set in between
+    /// polls are skipped. Ends when the current scope is disposed
+    fn to_stream(&self) -> BoxStream<'static, T> {
+        let changes = futures::stream::unfold((self.watch(), true), |(mut rx, first)| async move {
+            if !first && rx.changed().await.is_err() {
+                return None;
+            }
+            let value = rx.borrow_and_update().clone();
+            Some((value, (rx, false)))
+        });
+        changes.boxed()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static, S: SignalGet<T> + Clone + 'static> SignalWatch<T> for S {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ratatui::widget_ref, testing::TestApp};
+    use std::time::Duration;
+
+    #[test]
+    fn test_stream_fold_resubscribes() {
+        let (mut app, step) = TestApp::mount(20, 1, || {
+            let step = create_signal(1);
+            let total = create_stream_fold(
+                vec![],
+                move || {
+                    let step = step.get();
+                    futures::stream::unfold(0, move |n| async move {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        Some((n + step, n + step))
+                    })
+                },
+                |items: &mut Vec<i32>, item| items.push(item),
+            );
+            let latest = create_stream_signal(0, || futures::stream::iter([1, 2, 3]));
+            (
+                widget_ref(move || format!("{:?} {}", total.get(), latest.get())),
+                step,
+            )
+        });
+        app.advance(Duration::from_millis(25));
+        assert_eq!(app.to_string(), "[1, 2] 3\n");
+
+        app.enter(|| step.set(10));
+        app.advance(Duration::from_millis(20));
+        assert_eq!(app.to_string(), "[1, 2, 10, 20] 3\n");
+    }
+
+    #[test]
+    fn test_signal_to_stream() {
+        let (mut app, (query, seen)) = TestApp::mount(20, 1, || {
+            let query = create_signal("a");
+            let seen = create_signal(vec![]);
+            let seen_c = seen.clone();
+            on(move |query: &&str| seen_c.update(|s| s.push(*query)));
+            crate::tasks::create_async_task(create_signal(()), move |_, tx| {
+                let mut queries = query.to_stream().take(3);
+                async move {
+                    while let Some(query) = queries.next().await {
+                        tx.send(query);
+                    }
+                }
+            });
+            let values = (query, seen.clone());
+            (widget_ref(move || seen.get().join(",")), values)
+        });
+        app.run_until_idle();
+        app.enter(|| query.set("b"));
+        app.run_until_idle();
+        app.enter(|| {
+            query.set("c");
+            query.set("d");
+        });
+        app.run_until_idle();
+        assert_eq!(app.enter(|| seen.get()), vec!["a", "b", "d"]);
+        assert_eq!(app.to_string(), "a,b,d\n");
+    }
+}
@@ -1,5 +1,17 @@
-use futures::Future;
-use std::{any::Any, pin::Pin, sync::Arc};
+use futures::{Future, FutureExt};
+use std::{
+    any::{type_name, Any},
+    cell::RefCell,
+    collections::VecDeque,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{
     sync::{broadcast, mpsc, Mutex},
     task::JoinHandle,
@@ -11,9 +23,49 @@ use crate::{
     signal::*,
 };
 
-pub type Message = (Scope, Box<dyn Any + Send + 'static>);
+/// Scope that created the task, type name of the message and the message
+pub type Message = (Scope, &'static str, Box<dyn Any + Send + 'static>);
 pub type Task = (Scope, Pin<Box<dyn Future<Output = ()> + Send>>);
 
+/// Counts of the tasks spawned on a [`TaskRunner`]
+#[derive(Debug, Default)]
+struct Activity {
+    /// Spawned tasks that have not been polled yet
+    queued: AtomicUsize,
+    /// Tasks that were polled and have not finished
+    running: AtomicUsize,
+    /// Polls of every task
+    polls: AtomicUsize,
+}
+
+/// Moves a task from queued to running when created and removes it when dropped
+struct Running(Arc<Activity>);
+
+impl Running {
+    fn start(activity: Arc<Activity>) -> Self {
+        activity.queued.fetch_sub(1, Ordering::SeqCst);
+        activity.running.fetch_add(1, Ordering::SeqCst);
+        Self(activity)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Snapshot of the tasks spawned on a [`TaskRunner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskActivity {
+    /// Spawned tasks that have not been polled yet
+    pub queued: usize,
+    /// Tasks that were polled and have not finished
+    pub running: usize,
+    /// Polls of every task so far, unchanged while every running task is waiting
+    pub polls: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskRunner {
     message_rx: Arc<Mutex<mpsc::UnboundedReceiver<Message>>>,
@@ -22,6 +74,7 @@ pub struct TaskRunner {
     shutdown_tx: broadcast::Sender<()>,
     task_tx: mpsc::UnboundedSender<Task>,
     task_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    activity: Arc<Activity>,
 }
 
 impl TaskRunner {
@@ -32,14 +85,14 @@ impl TaskRunner {
         let (shutdown_tx, _) = broadcast::channel::<()>(100);
 
         let shutdown_tx_c = shutdown_tx.clone();
+        // Subscribe before spawning so a shutdown sent before the loop first runs is not missed
+        let mut shutdown_rx = shutdown_tx.subscribe();
         let task_handle = tokio::spawn(async move {
             loop {
-                let shutdown_tx = shutdown_tx_c.clone();
-                let mut shutdown_rx = shutdown_tx.subscribe();
                 tokio::select! {
                     task = task_rx.recv() => {
                         if let Some(task) = task {
-                            Self::spawn_task(task, shutdown_tx).await
+                            Self::spawn_task(task, shutdown_tx_c.clone()).await
                         }
                     },
                     _ = shutdown_rx.recv() => {
@@ -56,6 +109,7 @@ impl TaskRunner {
             shutdown_tx,
             task_tx,
             task_handle: Arc::new(Mutex::new(Some(task_handle))),
+            activity: Arc::default(),
         }
     }
 
@@ -69,8 +123,7 @@ impl TaskRunner {
         });
     }
 
-    async fn await_cancel(cancel_tx: broadcast::Sender<Scope>, task_id: Scope) {
-        let mut cancel = cancel_tx.subscribe();
+    async fn await_cancel(mut cancel: broadcast::Receiver<Scope>, task_id: Scope) {
         loop {
             if let Ok(id) = cancel.recv().await {
                 if task_id == id {
@@ -80,13 +133,112 @@ impl TaskRunner {
         }
     }
 
+    /// Waits for the next task message and delivers it to the scope that created the task,
+    /// messages from tasks whose scope has since been disposed are dropped
     pub async fn listen(&self) {
-        if let Some((_, message)) = self.message_rx.lock().await.recv().await {
-            let message = message as Box<dyn Any + 'static>;
-            send_boxed(&message);
+        if let Some(message) = self.recv().await {
+            Self::deliver(message);
+        }
+    }
+
+    /// Delivers a message that is already waiting, returns false when there was none.
+    /// Calling it until it returns false after [`TaskRunner::listen`] handles a burst of
+    /// messages before the next frame
+    pub fn try_listen(&self) -> bool {
+        match self.try_recv() {
+            Some(message) => {
+                Self::deliver(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Waits for the next task message without delivering it
+    pub async fn recv(&self) -> Option<Message> {
+        self.message_rx.lock().await.recv().await
+    }
+
+    /// A task message that is already waiting, not delivered
+    pub fn try_recv(&self) -> Option<Message> {
+        match self.message_rx.try_lock() {
+            Ok(mut rx) => rx.try_recv().ok(),
+            Err(_) => None,
+        }
+    }
+
+    /// Sends a message received with [`TaskRunner::recv`] to the scope that created the task
+    /// and its descendants, dropped when the scope has since been disposed
+    pub fn deliver((scope, _, message): Message) {
+        let message = message as Box<dyn Any + 'static>;
+        with_runtime(|r| {
+            if r.nodes.contains(scope) {
+                r.send(scope, &message, true);
+            }
+        });
+    }
+
+    /// Runs the future on the runner and delivers its output to scope and its descendants
+    pub fn spawn_message<M: Send + Any + 'static>(
+        &self,
+        scope: Scope,
+        fut: impl Future<Output = M> + Send + 'static,
+    ) {
+        let message_tx = self.message_tx.clone();
+        self.submit(scope, async move {
+            let _ = message_tx.send((scope, type_name::<M>(), Box::new(fut.await)));
+        });
+    }
+
+    /// Runs the future on the runner until it completes or [`TaskRunner::cancel`] is called
+    /// with id
+    pub(crate) fn spawn_cancellable(
+        &self,
+        id: Scope,
+        fut: impl Future<Output = ()> + Send + 'static,
+    ) {
+        // Subscribe now so a cancel sent before the task first runs is not missed
+        let cancel = self.cancel_tx.subscribe();
+        self.submit(id, async move {
+            tokio::select! {
+                _ = Self::await_cancel(cancel, id) => {},
+                _ = fut => {},
+            }
+        });
+    }
+
+    /// Sends the future to the runner loop counting its polls in [`TaskRunner::activity`]
+    fn submit(&self, id: Scope, fut: impl Future<Output = ()> + Send + 'static) {
+        let activity = self.activity.clone();
+        activity.queued.fetch_add(1, Ordering::SeqCst);
+        let mut fut = Box::pin(fut);
+        let mut running = None;
+        let future = futures::future::poll_fn(move |cx| {
+            activity.polls.fetch_add(1, Ordering::SeqCst);
+            running.get_or_insert_with(|| Running::start(activity.clone()));
+            fut.as_mut().poll(cx)
+        });
+        let _ = self.task_tx.send((id, Box::pin(future)));
+    }
+
+    /// Tasks spawned on the runner that have not finished
+    pub fn activity(&self) -> TaskActivity {
+        TaskActivity {
+            queued: self.activity.queued.load(Ordering::SeqCst),
+            running: self.activity.running.load(Ordering::SeqCst),
+            polls: self.activity.polls.load(Ordering::SeqCst),
         }
     }
 
+    pub(crate) fn cancel(&self, id: Scope) {
+        let _ = self.cancel_tx.send(id);
+    }
+
+    /// Sends messages from a task to scope and its descendants
+    pub(crate) fn transmitter(&self, scope: Scope) -> TaskMessageTransmitter {
+        TaskMessageTransmitter(scope, self.message_tx.clone())
+    }
+
     pub async fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
         if let Some(handle) = self.task_handle.lock().await.take() {
@@ -99,11 +251,11 @@ impl TaskRunner {
 pub struct TaskMessageTransmitter(Scope, mpsc::UnboundedSender<Message>);
 impl TaskMessageTransmitter {
     pub fn send<T: Send + Any + 'static>(&self, x: T) {
-        let _ = self.1.send((self.0, Box::new(x)));
+        let _ = self.1.send((self.0, type_name::<T>(), Box::new(x)));
     }
 }
 
-pub async fn create_async_scope<T, F>(f: impl FnOnce(TaskRunner) -> F)
+pub async fn create_async_scope<T, F>(f: impl FnOnce(TaskRunner) -> F) -> T
 where
     F: Future<Output = T>,
 {
@@ -113,92 +265,779 @@ where
     let runtime = TaskRunner::new().await;
     provide_context(runtime.clone());
 
-    f(runtime).await;
+    let result = f(runtime).await;
 
-    with_runtime(|s| s.tracker.replace(previous));
     with_runtime(|r| {
         r.tracker.replace(previous);
-        r.cleanup_child_scope(scope);
-        r.nodes.dispose(scope);
+        r.dispose(scope);
     });
+    result
 }
 
-pub fn create_async_task<Value, Fu>(
+/// Run of a task, runs are numbered from 1 in the order they are created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RunId {
+    task: Scope,
+    run: u64,
+}
+
+/// Progress of a run sent to the scope that created the task
+struct RunState {
+    id: RunId,
+    state: TaskState,
+}
+
+/// Final state and output of a run sent to the scope that created the task
+struct TaskOutput<T> {
+    id: RunId,
+    state: TaskState,
+    result: Result<T, TaskError>,
+}
+
+/// Runs fut with the value of arg on the [`TaskRunner`] in context and again whenever arg
+/// changes, cancelling the previous run. The state and output of the last run are available
+/// from the [`TaskControl`], messages of cancelled runs that arrive late are ignored
+pub fn create_async_task<Value, T, Fu>(
     arg: impl SignalGet<Value> + 'static,
     fut: impl Fn(Value, TaskMessageTransmitter) -> Fu + 'static,
-) -> TaskControl
+) -> TaskControl<T>
 where
     Value: Clone + 'static,
-    Fu: Future<Output = ()> + Send + 'static,
+    T: Clone + Send + 'static,
+    Fu: Future<Output = T> + Send + 'static,
+{
+    create_async_task_in(Concurrency::Switch, arg, fut)
+}
+
+/// Like [`create_async_task`] with changes to arg while a run has not finished handled by
+/// concurrency, queued runs start once a running one finishes and are dropped by
+/// [`TaskControl::stop`]
+pub fn create_async_task_in<Value, T, Fu>(
+    concurrency: Concurrency,
+    arg: impl SignalGet<Value> + 'static,
+    fut: impl Fn(Value, TaskMessageTransmitter) -> Fu + 'static,
+) -> TaskControl<T>
+where
+    Value: Clone + 'static,
+    T: Clone + Send + 'static,
+    Fu: Future<Output = T> + Send + 'static,
+{
+    create_task(concurrency, arg, move |data, tx, _| {
+        let inner = fut(data, tx);
+        async move { Ok(inner.await) }
+    })
+}
+
+/// Like [`create_async_task`] for futures that can fail, failed and timed out attempts are
+/// retried as configured by policy. While waiting to retry the state is
+/// [`TaskState::Retrying`], the error of the last attempt is the result when none succeed.
+/// Changes to arg while a run has not finished are handled by its [`Concurrency`]
+pub fn create_async_task_with<Value, T, E, Fu>(
+    policy: TaskPolicy,
+    arg: impl SignalGet<Value> + 'static,
+    fut: impl Fn(Value, TaskMessageTransmitter) -> Fu + Send + Sync + 'static,
+) -> TaskControl<T>
+where
+    Value: Clone + Send + Sync + 'static,
+    T: Clone + Send + 'static,
+    E: std::fmt::Display,
+    Fu: Future<Output = Result<T, E>> + Send + 'static,
+{
+    let fut = Arc::new(fut);
+    create_task(policy.concurrency, arg, move |data, tx, id| {
+        let fut = fut.clone();
+        async move {
+            let attempt = || fut(data.clone(), tx.clone());
+            let retrying = |attempt| {
+                let state = TaskState::Retrying { attempt };
+                tx.send(RunState { id, state })
+            };
+            policy.run(attempt, retrying).await
+        }
+    })
+}
+
+type RunFuture<T> = Pin<Box<dyn Future<Output = Result<T, TaskError>> + Send>>;
+
+/// Runs of a task that started and are waiting to start
+struct Runs<T> {
+    tasks: TaskRunner,
+    owner: Scope,
+    concurrency: Concurrency,
+    /// Number of the last run created
+    created: u64,
+    /// Number of the last run started
+    started: u64,
+    running: usize,
+    queue: VecDeque<(RunId, RunFuture<T>)>,
+    /// Number of the run whose output is the result and its final state
+    latest: u64,
+    settled: TaskState,
+}
+
+impl<T: Send + 'static> Runs<T> {
+    /// Spawns the run and sends its progress and output to the owner, the run listens for
+    /// cancellation from here on
+    fn start(&mut self, id: RunId, run: RunFuture<T>) {
+        self.running += 1;
+        self.started = self.started.max(id.run);
+        let cancel = self.tasks.cancel_tx.subscribe();
+        let tx = self.tasks.transmitter(self.owner);
+        self.tasks.submit(id.task, async move {
+            tx.send(RunState {
+                id,
+                state: TaskState::Pending,
+            });
+            let (state, result) = tokio::select! {
+                _ = TaskRunner::await_cancel(cancel, id.task) => {
+                    (TaskState::Cancelled, Err(TaskError::Cancelled))
+                },
+                output = AssertUnwindSafe(run).catch_unwind() => match output {
+                    Ok(Ok(output)) => (TaskState::Finnished, Ok(output)),
+                    Ok(Err(e)) => (TaskState::Failed, Err(e)),
+                    Err(panic) => (TaskState::Failed, Err(TaskError::from_panic(panic))),
+                },
+            };
+            tx.send(TaskOutput { id, state, result });
+        });
+    }
+
+    /// Records that run finished and starts the next queued run, returns whether its output
+    /// becomes the result. With [`Concurrency::Switch`] only the last run created counts,
+    /// otherwise a run counts unless a run created after it finished first
+    fn finish(&mut self, output: &TaskOutput<T>) -> bool {
+        self.running -= 1;
+        if let Some((id, run)) = self.queue.pop_front() {
+            self.start(id, run);
+        }
+        let latest = match self.concurrency {
+            Concurrency::Switch => output.id.run == self.created,
+            _ => output.id.run > self.latest,
+        };
+        if latest {
+            self.latest = output.id.run;
+            self.settled = output.state;
+        }
+        latest
+    }
+
+    fn busy(&self) -> bool {
+        self.running + self.queue.len() > 0
+    }
+}
+
+/// Spawns the future made by run whenever arg changes and reports its state and output to
+/// the [`TaskControl`], run is also given the id of the run
+fn create_task<Value, T, Fu>(
+    concurrency: Concurrency,
+    arg: impl SignalGet<Value> + 'static,
+    run: impl Fn(Value, TaskMessageTransmitter, RunId) -> Fu + 'static,
+) -> TaskControl<T>
+where
+    Value: Clone + 'static,
+    T: Clone + Send + 'static,
+    Fu: Future<Output = Result<T, TaskError>> + Send + 'static,
 {
     let status = create_signal(TaskState::Initial);
+    let result = create_signal(None);
     let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let runs = Rc::new(RefCell::new(Runs {
+        tasks: tasks.clone(),
+        owner,
+        concurrency,
+        created: 0,
+        started: 0,
+        running: 0,
+        queue: VecDeque::new(),
+        latest: 0,
+        settled: TaskState::Initial,
+    }));
 
-    let canceller = tasks.cancel_tx.clone();
-    let message_tx = tasks.message_tx.clone();
-
+    let runs_c = runs.clone();
     let id = create_memo(move || {
         let data = arg.get();
 
         let id = with_runtime(|s| s.tracker.get()).unwrap();
+        let mut runs = runs_c.borrow_mut();
+        let start = match concurrency {
+            Concurrency::Switch => true,
+            Concurrency::Exhaust if runs.running > 0 => return,
+            Concurrency::Exhaust => true,
+            Concurrency::Queue => runs.running == 0,
+            Concurrency::Parallel { max } => runs.running < max.max(1),
+        };
+        runs.created += 1;
+        let run_id = RunId {
+            task: id,
+            run: runs.created,
+        };
+        drop(runs);
 
-        let canceller_c = canceller.clone();
-        let message_tx = message_tx.clone();
-        let inner = fut(data, TaskMessageTransmitter(id, message_tx.clone()));
-        let future = Box::pin(async move {
-            let _ = message_tx.send((id, Box::new((id, TaskState::Pending))));
-            tokio::select! {
-                _ = TaskRunner::await_cancel(canceller_c, id) => {
-                    let _ = message_tx.send((id, Box::new((id, TaskState::Cancelled))));
-                },
-                _ = inner => {
-                    let _ = message_tx.send((id, Box::new((id, TaskState::Finnished))));
-                },
-            }
-        });
+        let future = Box::pin(run(data, tasks.transmitter(owner), run_id));
+        match start {
+            true => runs_c.borrow_mut().start(run_id, future),
+            false => runs_c.borrow_mut().queue.push_back((run_id, future)),
+        }
+        if concurrency == Concurrency::Switch {
+            let tasks = tasks.clone();
+            on_cleanup(move || tasks.cancel(id))
+        }
+    })
+    .into_scope();
+    let runs_c = runs.clone();
+    on_cleanup(move || {
+        let mut runs = runs_c.borrow_mut();
+        runs.queue.clear();
+        runs.tasks.cancel(id);
+    });
 
-        let _ = tasks.task_tx.send((id, future));
-        let canceller = canceller.clone();
-        on_cleanup(move || {
-            let _ = canceller.send(id);
-        })
+    let (status_c, runs_c) = (status, runs.clone());
+    on(move |RunState { id: run_id, state }: &RunState| {
+        // Only the last run started reports its progress
+        if run_id.task == id && run_id.run == runs_c.borrow().started {
+            status_c.set(*state);
+        }
     });
 
-    let status_c = status.clone();
-    on(move |(task_id, ev): &(Scope, TaskState)| {
-        if *task_id != id.into_scope() {
+    let (status_c, result_c, runs_c) = (status, result.clone(), runs.clone());
+    on(move |output: &TaskOutput<T>| {
+        if output.id.task != id {
             return;
         }
-        status_c.set(*ev);
+        let mut runs = runs_c.borrow_mut();
+        let latest = runs.finish(output);
+        let state = match runs.busy() {
+            true => None,
+            false => Some(runs.settled),
+        };
+        drop(runs);
+
+        if latest {
+            result_c.set(Some(output.result.clone()));
+        }
+        match state {
+            Some(state) => status_c.set(state),
+            None if !status_c.get_untracked().is_running() => status_c.set(TaskState::Pending),
+            None => {}
+        }
     });
 
     TaskControl {
-        id: id.into_scope(),
-        cancel_tx: tasks.cancel_tx.clone(),
+        id,
+        runs,
         status,
+        result,
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct TaskControl {
+#[derive(Clone)]
+pub struct TaskControl<T = ()> {
     id: Scope,
-    cancel_tx: broadcast::Sender<Scope>,
+    runs: Rc<RefCell<Runs<T>>>,
     status: Signal<TaskState>,
+    result: Signal<Option<Result<T, TaskError>>>,
 }
 
-impl TaskControl {
+impl<T: Clone + 'static> TaskControl<T> {
+    /// Pending while a run is running or queued, otherwise the final state of the run whose
+    /// output is the result
     pub fn get_state(&self) -> TaskState {
         self.status.get()
     }
+    /// Cancels the running runs and drops the queued ones
     pub fn stop(&self) {
-        let _ = self.cancel_tx.send(self.id);
+        let mut runs = self.runs.borrow_mut();
+        runs.queue.clear();
+        runs.tasks.cancel(self.id);
+    }
+    /// Output of the last run, None until one finishes. With [`Concurrency::Switch`] that is
+    /// the run for the current argument, otherwise the last created run that finished
+    pub fn result(&self) -> Option<Result<T, TaskError>> {
+        self.result.get()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl<T: std::fmt::Debug + Clone + 'static> std::fmt::Debug for TaskControl<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskControl")
+            .field("id", &self.id)
+            .field("status", &self.status)
+            .field("result", &self.result)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TaskState {
     Initial,
     Pending,
     Cancelled,
     Finnished,
+    /// The task panicked or every attempt failed
+    Failed,
+    /// Waiting to start the attempt after a failure
+    Retrying {
+        attempt: u32,
+    },
+}
+
+impl TaskState {
+    fn is_running(&self) -> bool {
+        matches!(self, TaskState::Pending | TaskState::Retrying { .. })
+    }
+}
+
+/// Why a task did not produce an output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskError {
+    Cancelled,
+    /// The task panicked with the message
+    Panicked(String),
+    /// The attempt took longer than the timeout of its [`TaskPolicy`]
+    TimedOut,
+    /// The task returned the error
+    Failed(String),
+}
+
+impl TaskError {
+    fn from_panic(panic: Box<dyn Any + Send>) -> Self {
+        let message = match panic.downcast::<String>() {
+            Ok(message) => *message,
+            Err(panic) => match panic.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Unknown panic".to_string(),
+            },
+        };
+        Self::Panicked(message)
+    }
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskError::Cancelled => write!(f, "Task was cancelled"),
+            TaskError::Panicked(message) => write!(f, "Task panicked: {}", message),
+            TaskError::TimedOut => write!(f, "Task timed out"),
+            TaskError::Failed(message) => write!(f, "Task failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+/// How [`create_async_task_with`] retries failed attempts. The delay before attempt n + 1 is
+/// backoff * 2^(n - 1) up to max_backoff, reduced by a random fraction of up to jitter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaskPolicy {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: f64,
+    /// Limit for each attempt
+    pub timeout: Option<Duration>,
+    /// How changes to the argument are handled while a run has not finished, see
+    /// [`create_async_task_in`] to choose it without retries
+    pub concurrency: Concurrency,
+}
+
+impl Default for TaskPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.1,
+            timeout: None,
+            concurrency: Concurrency::Switch,
+        }
+    }
+}
+
+impl TaskPolicy {
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Fraction between 0 and 1
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: Concurrency) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Delay after the failed attempt, jitter outside 0 to 1 is clamped
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.backoff.saturating_mul(factor).min(self.max_backoff);
+        let jitter = match self.jitter.is_nan() {
+            true => 0.0,
+            false => self.jitter.clamp(0.0, 1.0),
+        };
+        delay.mul_f64(1.0 - jitter * random_fraction())
+    }
+
+    async fn run<T, E, Fu>(
+        &self,
+        attempt: impl Fn() -> Fu,
+        retrying: impl Fn(u32),
+    ) -> Result<T, TaskError>
+    where
+        E: std::fmt::Display,
+        Fu: Future<Output = Result<T, E>>,
+    {
+        let mut n = 1;
+        loop {
+            let failed = |e: E| TaskError::Failed(e.to_string());
+            let result = match self.timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, attempt()).await {
+                    Ok(result) => result.map_err(failed),
+                    Err(_) => Err(TaskError::TimedOut),
+                },
+                None => attempt().await.map_err(failed),
+            };
+            let error = match result {
+                Ok(output) => return Ok(output),
+                Err(error) => error,
+            };
+            if n >= self.max_attempts {
+                return Err(error);
+            }
+            retrying(n + 1);
+            tokio::time::sleep(self.delay(n)).await;
+            n += 1;
+        }
+    }
+}
+
+/// What a task does when its argument changes while a run has not finished
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Concurrency {
+    /// Cancel the running run and start a new one
+    #[default]
+    Switch,
+    /// Start the new run after the running ones finish
+    Queue,
+    /// Ignore changes while a run has not finished
+    Exhaust,
+    /// Run up to max at once and queue the rest
+    Parallel { max: usize },
+}
+
+/// Between 0 and 1, randomly seeded per call
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (random >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratatui::{component, widget_ref};
+
+    #[tokio::test]
+    async fn test_task_messages_reach_spawning_scope() {
+        create_async_scope(|runner| async move {
+            let spawn = move |send_message: bool| {
+                let recieved = create_signal(0);
+                component(move || {
+                    on(move |_: &&str| recieved.update(|v| *v += 1));
+                    create_async_task(create_signal(send_message), |send_message, tx| async move {
+                        if send_message {
+                            tx.send("done");
+                        }
+                    });
+                    widget_ref(|| "")
+                });
+                recieved
+            };
+            let a = spawn(false);
+            let b = spawn(true);
+
+            for _ in 0..5 {
+                runner.listen().await;
+            }
+            assert_eq!((a.get(), b.get()), (0, 1));
+            runner.shutdown().await;
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_try_listen_drains_ready_messages() {
+        create_async_scope(|runner| async move {
+            let recieved = create_signal(0);
+            on(move |_: &usize| recieved.update(|v| *v += 1));
+            let scope = with_runtime(|r| r.get_current_scope());
+            for i in 0..3_usize {
+                runner
+                    .message_tx
+                    .send((scope, "usize", Box::new(i)))
+                    .unwrap();
+            }
+
+            runner.listen().await;
+            assert_eq!(recieved.get(), 1);
+            while runner.try_listen() {}
+            assert_eq!(recieved.get(), 3);
+            assert!(!runner.try_listen());
+            runner.shutdown().await;
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_task_result_and_panic() {
+        let (mut app, (input, task)) = crate::testing::TestApp::mount(10, 1, || {
+            let input = create_signal(2);
+            let task = create_async_task(input, |n: i32, _| async move {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                if n < 0 {
+                    panic!("negative");
+                }
+                n * 2
+            });
+            (widget_ref(|| ""), (input, task))
+        });
+        assert_eq!(app.enter(|| task.result()), None);
+        app.advance(std::time::Duration::from_millis(10));
+        assert_eq!(app.enter(|| task.result()), Some(Ok(4)));
+
+        app.enter(|| input.set(-1));
+        app.advance(std::time::Duration::from_millis(10));
+        let panicked = TaskError::Panicked("negative".to_string());
+        assert_eq!(app.enter(|| task.result()), Some(Err(panicked)));
+        assert_eq!(app.enter(|| task.get_state()), TaskState::Failed);
+    }
+
+    #[test]
+    fn test_task_ignores_replaced_runs() {
+        let ms = Duration::from_millis;
+        let (mut app, (input, task)) = crate::testing::TestApp::mount(10, 1, || {
+            let input = create_signal(1);
+            let task = create_async_task(input, |n: i32, _| async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                n * 2
+            });
+            (widget_ref(|| ""), (input, task))
+        });
+        app.advance(ms(10));
+        assert_eq!(app.enter(|| task.result()), Some(Ok(2)));
+
+        app.enter(|| input.set(2));
+        app.advance(ms(5));
+        app.enter(|| input.set(3));
+        app.advance(ms(5));
+        assert_eq!(app.enter(|| task.get_state()), TaskState::Pending);
+        assert_eq!(app.enter(|| task.result()), Some(Ok(2)));
+
+        app.advance(ms(5));
+        assert_eq!(app.enter(|| task.get_state()), TaskState::Finnished);
+        assert_eq!(app.enter(|| task.result()), Some(Ok(6)));
+    }
+
+    #[test]
+    fn test_queued_task_state_and_stop() {
+        let ms = Duration::from_millis;
+        let started = Arc::new(std::sync::Mutex::new(vec![]));
+        let started_c = started.clone();
+        let (mut app, (input, task)) = crate::testing::TestApp::mount(10, 1, || {
+            let input = create_signal(0);
+            let task = create_async_task_in(Concurrency::Queue, input, move |n: i32, _| {
+                let started = started_c.clone();
+                async move {
+                    started.lock().unwrap().push(n);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    n
+                }
+            });
+            (widget_ref(|| ""), (input, task))
+        });
+        let state = |app: &crate::testing::TestApp| app.enter(|| (task.get_state(), task.result()));
+        app.enter(|| (1..=2).for_each(|n| input.set(n)));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Pending, Some(Ok(0))));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Pending, Some(Ok(1))));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Finnished, Some(Ok(2))));
+
+        app.enter(|| (3..=4).for_each(|n| input.set(n)));
+        app.advance(ms(5));
+        app.enter(|| task.stop());
+        app.advance(ms(5));
+        let cancelled = (TaskState::Cancelled, Some(Err(TaskError::Cancelled)));
+        assert_eq!(state(&app), cancelled);
+        app.advance(ms(30));
+        assert_eq!(state(&app), cancelled);
+        assert_eq!(*started.lock().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_parallel_task_state_and_stop() {
+        let ms = Duration::from_millis;
+        let (mut app, (input, task)) = crate::testing::TestApp::mount(10, 1, || {
+            let input = create_signal(0);
+            let concurrency = Concurrency::Parallel { max: 2 };
+            let task = create_async_task_in(concurrency, input, |n: i32, _| async move {
+                let duration = if n == 0 { 20 } else { 10 };
+                tokio::time::sleep(Duration::from_millis(duration)).await;
+                n
+            });
+            (widget_ref(|| ""), (input, task))
+        });
+        let state = |app: &crate::testing::TestApp| app.enter(|| (task.get_state(), task.result()));
+        app.enter(|| input.set(1));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Pending, Some(Ok(1))));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Finnished, Some(Ok(1))));
+
+        app.enter(|| (2..=4).for_each(|n| input.set(n)));
+        app.advance(ms(5));
+        app.enter(|| task.stop());
+        app.advance(ms(5));
+        let cancelled = (TaskState::Cancelled, Some(Err(TaskError::Cancelled)));
+        assert_eq!(state(&app), cancelled);
+        app.advance(ms(30));
+        assert_eq!(state(&app), cancelled);
+    }
+
+    #[test]
+    fn test_exhaust_task_runs_again_after_finishing() {
+        let ms = Duration::from_millis;
+        let (mut app, (input, task)) = crate::testing::TestApp::mount(10, 1, || {
+            let input = create_signal(0);
+            let task = create_async_task_in(Concurrency::Exhaust, input, |n: i32, _| async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                n
+            });
+            (widget_ref(|| ""), (input, task))
+        });
+        let state = |app: &crate::testing::TestApp| app.enter(|| (task.get_state(), task.result()));
+        app.enter(|| input.set(1));
+        app.advance(ms(10));
+        assert_eq!(state(&app), (TaskState::Finnished, Some(Ok(0))));
+
+        app.enter(|| input.set(2));
+        app.advance(ms(5));
+        assert_eq!(state(&app), (TaskState::Pending, Some(Ok(0))));
+        app.advance(ms(5));
+        assert_eq!(state(&app), (TaskState::Finnished, Some(Ok(2))));
+    }
+
+    #[test]
+    fn test_task_policy_retries_and_times_out() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        fn ms(ms: u64) -> Duration {
+            Duration::from_millis(ms)
+        }
+        let (mut app, (flaky, slow)) = crate::testing::TestApp::mount(10, 1, || {
+            let policy = TaskPolicy::default().max_attempts(3).jitter(0.0);
+            let attempts = Arc::new(AtomicU32::new(0));
+            let flaky = create_async_task_with(policy, create_signal(()), move |_, _| {
+                let attempts = attempts.clone();
+                async move {
+                    tokio::time::sleep(ms(10)).await;
+                    match attempts.fetch_add(1, Ordering::SeqCst) + 1 {
+                        n if n < 3 => Err("busy"),
+                        n => Ok(n),
+                    }
+                }
+            });
+            let policy = policy.max_attempts(2).timeout(ms(50));
+            let slow = create_async_task_with(policy, create_signal(()), |_, _| async {
+                tokio::time::sleep(ms(1000)).await;
+                Ok::<_, String>(())
+            });
+            (widget_ref(|| ""), (flaky, slow))
+        });
+        app.advance(ms(15));
+        let state = app.enter(|| flaky.get_state());
+        assert_eq!(state, TaskState::Retrying { attempt: 2 });
+        app.advance(ms(185));
+        assert_eq!(app.enter(|| slow.result()), Some(Err(TaskError::TimedOut)));
+        assert_eq!(app.enter(|| slow.get_state()), TaskState::Failed);
+        let state = app.enter(|| flaky.get_state());
+        assert_eq!(state, TaskState::Retrying { attempt: 3 });
+        app.advance(ms(130));
+        assert_eq!(app.enter(|| flaky.result()), Some(Ok(3)));
+        assert_eq!(app.enter(|| flaky.get_state()), TaskState::Finnished);
+    }
+
+    #[test]
+    fn test_task_policy_backoff() {
+        let ms = Duration::from_millis;
+        let policy = TaskPolicy::default().backoff(ms(100), ms(300)).jitter(0.0);
+        let delays: Vec<_> = (1..=4).map(|n| policy.delay(n)).collect();
+        assert_eq!(delays, vec![ms(100), ms(200), ms(300), ms(300)]);
+        let delay = policy.jitter(0.5).delay(2);
+        assert!(delay >= ms(100) && delay <= ms(200));
+        let unclamped = TaskPolicy {
+            jitter: 3.0,
+            ..policy
+        };
+        assert!(unclamped.delay(2) <= ms(200));
+    }
+
+    #[test]
+    fn test_concurrency_strategies() {
+        let ms = Duration::from_millis;
+        let strategies = [
+            Concurrency::Switch,
+            Concurrency::Queue,
+            Concurrency::Exhaust,
+            Concurrency::Parallel { max: 2 },
+        ];
+        let (mut app, (arg, done)) = crate::testing::TestApp::mount(10, 1, || {
+            let arg = create_signal(0);
+            let done = create_signal(vec![]);
+            let done_c = done.clone();
+            on(move |run: &(usize, i32)| done_c.update(|d| d.push(*run)));
+            for (i, concurrency) in strategies.into_iter().enumerate() {
+                create_async_task_in(concurrency, arg, move |n, tx| async move {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    tx.send((i, n));
+                });
+            }
+            (widget_ref(|| ""), (arg, done))
+        });
+        app.enter(|| (1..=3).for_each(|n| arg.set(n)));
+        let finished = |app: &crate::testing::TestApp, strategy| {
+            let done = app.enter(|| done.get());
+            let runs = done.into_iter().filter(|(i, _)| *i == strategy);
+            runs.map(|(_, n)| n).collect::<Vec<_>>()
+        };
+
+        app.advance(ms(100));
+        assert_eq!(finished(&app, 0), vec![3]);
+        assert_eq!(finished(&app, 1), vec![0]);
+        assert_eq!(finished(&app, 2), vec![0]);
+        assert_eq!(finished(&app, 3), vec![0, 1]);
+
+        app.advance(ms(300));
+        assert_eq!(finished(&app, 0), vec![3]);
+        assert_eq!(finished(&app, 1), vec![0, 1, 2, 3]);
+        assert_eq!(finished(&app, 2), vec![0]);
+        assert_eq!(finished(&app, 3), vec![0, 1, 2, 3]);
+    }
 }
@@ -0,0 +1,528 @@
+use std::{any::Any, cell::RefCell, collections::VecDeque, fmt::Write, path::Path, time::Duration};
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    backend::TestBackend,
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::Color,
+    Terminal,
+};
+
+use crate::{
+    environment::*,
+    events::send_event,
+    keymap::KeySequence,
+    mouse::begin_frame,
+    nodes::{ReactiveNode, Scope},
+    ratatui::RatView,
+    record::{Recorded, Recording},
+    tasks::{Message, TaskRunner},
+};
+
+/// Runs a [`TaskRunner`] on a current thread runtime with paused time so tasks only make
+/// progress inside [`TestExecutor::run_until_idle`] and [`TestExecutor::advance`].
+/// Must be used from a synchronous test
+pub struct TestExecutor {
+    runtime: tokio::runtime::Runtime,
+    tasks: TaskRunner,
+    start: tokio::time::Instant,
+    /// Task messages kept from their scopes while replaying, see [`TestExecutor::hold`]
+    held: RefCell<Option<VecDeque<Message>>>,
+}
+
+impl TestExecutor {
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .expect("Failed to build test runtime");
+        let tasks = runtime.block_on(TaskRunner::new());
+        let start = runtime.block_on(async { tokio::time::Instant::now() });
+        Self {
+            runtime,
+            tasks,
+            start,
+            held: RefCell::default(),
+        }
+    }
+
+    /// The runner to provide as context for the components under test
+    pub fn tasks(&self) -> TaskRunner {
+        self.tasks.clone()
+    }
+
+    /// Runs tasks without moving time and delivers their messages in the current scope until
+    /// no message is waiting and every spawned task is waiting on time or has finished
+    pub fn run_until_idle(&self) {
+        let tasks = self.tasks.clone();
+        self.runtime.block_on(async move {
+            loop {
+                let before = tasks.activity();
+                // Every task that can make progress is polled before this resumes
+                tokio::task::yield_now().await;
+                let mut delivered = false;
+                while let Some(message) = tasks.try_recv() {
+                    self.receive(message);
+                    delivered = true;
+                }
+                let after = tasks.activity();
+                let waiting = after.running == 0 || after.polls == before.polls;
+                if !delivered && after.queued == 0 && waiting {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Moves virtual time forward firing timers in order, messages are delivered as they are
+    /// sent
+    pub fn advance(&self, duration: Duration) {
+        let tasks = self.tasks.clone();
+        self.runtime.block_on(async move {
+            let sleep = tokio::time::sleep(duration);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    biased;
+                    message = tasks.recv() => if let Some(message) = message {
+                        self.receive(message)
+                    },
+                    _ = &mut sleep => break,
+                }
+            }
+        });
+        self.run_until_idle();
+    }
+
+    /// Keeps task messages instead of delivering them until [`TestExecutor::release`], they
+    /// are delivered one at a time with [`TestExecutor::deliver_held`]
+    pub fn hold(&self) {
+        self.held.replace(Some(VecDeque::new()));
+    }
+
+    /// Delivers task messages as they arrive again, dropping the ones still held
+    pub fn release(&self) {
+        self.held.replace(None);
+    }
+
+    /// Delivers the oldest held message of the type, returns false when none was sent yet
+    pub fn deliver_held(&self, type_name: &str) -> bool {
+        let message = self.held.borrow_mut().as_mut().and_then(|held| {
+            let i = held.iter().position(|(_, name, _)| *name == type_name)?;
+            held.remove(i)
+        });
+        match message {
+            Some(message) => {
+                TaskRunner::deliver(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn receive(&self, message: Message) {
+        if let Some(held) = self.held.borrow_mut().as_mut() {
+            held.push_back(message);
+            return;
+        }
+        TaskRunner::deliver(message);
+    }
+
+    /// Virtual time elapsed since the executor was created
+    pub fn elapsed(&self) -> Duration {
+        self.runtime.block_on(async { self.start.elapsed() })
+    }
+}
+
+impl Default for TestExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestExecutor {
+    fn drop(&mut self) {
+        self.runtime.block_on(self.tasks.shutdown());
+    }
+}
+
+/// Renders a root view to an in memory terminal for tests, tasks created under the root run
+/// on a [`TestExecutor`]
+pub struct TestApp {
+    terminal: Terminal<TestBackend>,
+    scope: Scope,
+    root: RatView,
+    executor: TestExecutor,
+}
+
+impl TestApp {
+    pub fn new(width: u16, height: u16, root: impl FnOnce() -> RatView) -> Self {
+        let executor = TestExecutor::new();
+        with_runtime(|r| r.terminal_size.set(Rect::new(0, 0, width, height)));
+        let scope = with_runtime(|r| r.nodes.insert(ReactiveNode::default()));
+        let root = with_runtime(|r| {
+            r.with_tracking_scope(scope, || {
+                provide_context(executor.tasks());
+                root()
+            })
+        });
+        let terminal =
+            Terminal::new(TestBackend::new(width, height)).expect("Failed to create terminal");
+        let mut app = Self {
+            terminal,
+            scope,
+            root,
+            executor,
+        };
+        app.draw(true);
+        app
+    }
+
+    /// Like [`TestApp::new`] with root also returning values for the test, such as the
+    /// signals and handles it created
+    pub fn mount<T>(width: u16, height: u16, root: impl FnOnce() -> (RatView, T)) -> (Self, T) {
+        let mut values = None;
+        let app = Self::new(width, height, || {
+            let (view, created) = root();
+            values = Some(created);
+            view
+        });
+        (app, values.expect("Root is created by TestApp::new"))
+    }
+
+    /// Renders the root at the recorded screen size and replays the session, virtual time is
+    /// advanced to each entry so timers fire in the same order relative to the events as when
+    /// recording. Tasks run again and their messages are held until the time a message of the
+    /// same type was recorded at, messages that were not recorded are dropped
+    pub fn replay(recording: &Recording, root: impl FnOnce() -> RatView) -> Self {
+        let (width, height) = recording.size;
+        let mut app = Self::new(width, height, root);
+        app.executor.hold();
+        for (at, entry) in &recording.entries {
+            app.advance(at.saturating_sub(app.executor.elapsed()));
+            match entry {
+                Recorded::Event(Event::Resize(width, height)) => app.resize(*width, *height),
+                Recorded::Event(event) => app.event(event.clone()),
+                Recorded::Task(name) => {
+                    if !app.enter(|| app.executor.deliver_held(name)) {
+                        tracing::warn!("No {} task message to replay at {:?}", name, at);
+                    }
+                    app.draw(false);
+                }
+            }
+        }
+        app.executor.release();
+        app
+    }
+
+    /// Runs f in the root scope
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        with_runtime(|r| r.with_tracking_scope(self.scope, f))
+    }
+
+    pub fn send<T: Any + 'static>(&mut self, message: T) {
+        self.enter(|| send(message));
+        self.draw(false);
+    }
+
+    /// Sends a terminal event the way the app runner does
+    pub fn event(&mut self, event: Event) {
+        self.enter(|| send_event(event));
+        self.draw(false);
+    }
+
+    /// Presses each key of a space separated spec such as "ctrl+s" or "g g"
+    pub fn keys(&mut self, spec: &str) {
+        let keys = KeySequence::parse(spec).unwrap_or_else(|e| panic!("{}", e));
+        for key in keys.0 {
+            self.event(Event::Key(KeyEvent::new(key.code, key.modifiers)));
+        }
+    }
+
+    /// Presses the key of each character
+    pub fn type_text(&mut self, text: &str) {
+        for ch in text.chars() {
+            self.event(Event::Key(KeyEvent::from(KeyCode::Char(ch))));
+        }
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal.backend_mut().resize(width, height);
+        self.enter(|| send_event(Event::Resize(width, height)));
+        self.draw(true);
+    }
+
+    /// See [`TestExecutor::run_until_idle`]
+    pub fn run_until_idle(&mut self) {
+        self.enter(|| self.executor.run_until_idle());
+        self.draw(false);
+    }
+
+    /// See [`TestExecutor::advance`]
+    pub fn advance(&mut self, duration: Duration) {
+        self.enter(|| self.executor.advance(duration));
+        self.draw(false);
+    }
+
+    pub fn executor(&self) -> &TestExecutor {
+        &self.executor
+    }
+
+    /// Draws the root when a view changed or when forced
+    pub fn draw(&mut self, force: bool) {
+        if !(with_runtime(|r| r.scheduler.take_redraw()) || force) {
+            return;
+        }
+        let root = self.root;
+        begin_frame();
+        self.terminal
+            .draw(|f| f.render_widget_ref(root, f.size()))
+            .expect("Failed to draw");
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        self.terminal.backend().buffer()
+    }
+
+    /// The text followed by a line for each run of styled cells, `row:start..end style`
+    pub fn styled(&self) -> String {
+        let buf = self.buffer();
+        let mut out = buffer_to_string(buf);
+        out.push('\n');
+        for y in 0..buf.area.height {
+            let mut x = 0;
+            while x < buf.area.width {
+                let style = describe_style(buf.get(x, y));
+                let start = x;
+                while x < buf.area.width && describe_style(buf.get(x, y)) == style {
+                    x += 1;
+                }
+                if !style.is_empty() {
+                    let _ = writeln!(out, "{}:{}..{} {}", y, start, x, style);
+                }
+            }
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for TestApp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&buffer_to_string(self.buffer()))
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        with_runtime(|r| r.dispose(self.scope));
+    }
+}
+
+/// Rows of the buffer with trailing spaces removed
+pub fn buffer_to_string(buf: &Buffer) -> String {
+    let mut lines = vec![];
+    for y in 0..buf.area.height {
+        let mut line = String::new();
+        for x in 0..buf.area.width {
+            line.push_str(buf.get(x, y).symbol());
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines.join("\n") + "\n"
+}
+
+fn describe_style(cell: &Cell) -> String {
+    let mut parts = vec![];
+    if cell.fg != Color::Reset {
+        parts.push(format!("fg={:?}", cell.fg));
+    }
+    if cell.bg != Color::Reset {
+        parts.push(format!("bg={:?}", cell.bg));
+    }
+    if !cell.modifier.is_empty() {
+        parts.push(format!("{:?}", cell.modifier));
+    }
+    parts.join(" ")
+}
+
+/// Compares text with the snapshot file, the file is only written when
+/// `RIZZUP_UPDATE_SNAPSHOTS` is set
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os("RIZZUP_UPDATE_SNAPSHOTS").is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("Failed to create snapshot directory");
+        }
+        std::fs::write(path, actual).expect("Failed to write snapshot");
+        return;
+    }
+    let Ok(expected) = std::fs::read_to_string(path) else {
+        panic!(
+            "Missing snapshot {}, run with RIZZUP_UPDATE_SNAPSHOTS=1 to create it\n--- actual\n{}",
+            path.display(),
+            actual
+        );
+    };
+    if expected != actual {
+        panic!(
+            "Snapshot {} does not match, run with RIZZUP_UPDATE_SNAPSHOTS=1 to update it\n--- expected\n{}--- actual\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+}
+
+/// Compares the rendered text of a [`TestApp`] or any Display value with
+/// `tests/snapshots/<name>.snap` in the calling crate
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $actual:expr) => {
+        $crate::testing::assert_snapshot(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/snapshots")
+                .join(format!("{}.snap", $name)),
+            &$actual.to_string(),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use ratatui::{style::Stylize, widgets::*};
+
+    fn counter() -> RatView {
+        let count = create_signal(0);
+        let loaded = create_signal(false);
+        use_keymap([("+", 1), ("-", -1)]);
+        on(move |step: &i32| count.update(|c| *c += step));
+        create_async_task(create_signal(()), |_, tx| async move { tx.send(true) });
+        on(move |done: &bool| loaded.set(*done));
+        widget_ref(move || {
+            let block = Block::default().borders(Borders::all()).title("Counter");
+            let status = match loaded.get() {
+                true => "loaded".green(),
+                false => "loading".into(),
+            };
+            Paragraph::new(vec![count.get().to_string().into(), status.into()]).block(block)
+        })
+    }
+
+    #[test]
+    fn test_counter_snapshot() {
+        let mut app = TestApp::new(20, 4, counter);
+        app.keys("+ + + -");
+        crate::assert_snapshot!("counter", app);
+
+        app.run_until_idle();
+        assert!(app.to_string().contains("loaded"));
+        assert!(app.styled().ends_with("2:1..7 fg=Green\n"));
+    }
+
+    #[test]
+    fn test_missing_snapshot_not_written() {
+        if std::env::var_os("RIZZUP_UPDATE_SNAPSHOTS").is_some() {
+            return;
+        }
+        let path = std::env::temp_dir().join("rizzup-missing.snap");
+        let result = std::panic::catch_unwind(|| assert_snapshot(&path, "text"));
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_advance_virtual_time() {
+        let mut app = TestApp::new(10, 1, || {
+            let ticks = create_signal(0);
+            create_async_task(create_signal(()), |_, tx| async move {
+                loop {
+                    tx.send(());
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+            });
+            on(move |_: &()| ticks.update(|t| *t += 1));
+            widget_ref(move || ticks.get().to_string())
+        });
+        assert_eq!(app.to_string(), "0\n");
+        app.run_until_idle();
+        assert_eq!(app.to_string(), "1\n");
+        app.advance(Duration::from_millis(1200));
+        assert_eq!(app.to_string(), "3\n");
+        app.run_until_idle();
+        assert_eq!(app.to_string(), "3\n");
+        let elapsed = app.executor().elapsed();
+        assert_eq!(elapsed, Duration::from_millis(1200));
+    }
+
+    #[test]
+    fn test_run_until_idle_waits_for_busy_tasks() {
+        let mut app = TestApp::new(10, 1, || {
+            let done = create_signal(false);
+            create_async_task(create_signal(()), |_, tx| async move {
+                for _ in 0..500 {
+                    tokio::task::yield_now().await;
+                }
+                tx.send(true);
+            });
+            on(move |d: &bool| done.set(*d));
+            widget_ref(move || done.get().to_string())
+        });
+        app.run_until_idle();
+        assert_eq!(app.to_string(), "true\n");
+        assert_eq!(app.executor().tasks().activity().running, 0);
+    }
+
+    #[test]
+    fn test_replay_recording() {
+        let recording: Recording = "# rizzup recording 20 4\n\
+            100 key +\n\
+            400 key + none\n\
+            401 key +\n\
+            650 task bool\n\
+            900 key -\n"
+            .parse()
+            .unwrap();
+        let app = TestApp::replay(&recording, counter);
+        assert_eq!(app.to_string().lines().nth(1), Some("│2                 │"));
+        assert!(app.to_string().contains("loaded"));
+        assert_eq!(app.executor().elapsed(), Duration::from_millis(900));
+
+        let mut recording = recording;
+        recording.entries.truncate(3);
+        let app = TestApp::replay(&recording, counter);
+        assert!(app.to_string().contains("loading"), "Task message held");
+    }
+
+    #[test]
+    fn test_type_text_and_resize() {
+        let mut app = TestApp::new(10, 1, || {
+            let text = create_signal(String::new());
+            let text_c = text.clone();
+            on(move |key: &KeyEvent| {
+                if let KeyCode::Char(ch) = key.code {
+                    text_c.update(|t| t.push(ch))
+                }
+            });
+            widget_ref(move || text.get())
+        });
+        app.type_text("Hi there");
+        assert_eq!(app.to_string(), "Hi there\n");
+        app.resize(4, 1);
+        assert_eq!(app.to_string(), "Hi t\n");
+    }
+
+    #[test]
+    fn test_terminal_size_follows_app() {
+        let mut app = TestApp::new(12, 3, || {
+            let size = use_terminal_size();
+            widget_ref(move || format!("{}x{}", size.get().width, size.get().height))
+        });
+        assert_eq!(app.to_string(), "12x3\n\n\n");
+        app.resize(5, 2);
+        assert_eq!(app.to_string(), "5x2\n\n");
+    }
+}
@@ -0,0 +1,285 @@
+use std::{cell::Cell, marker::PhantomData, rc::Rc, time::Duration};
+
+use crate::{environment::*, nodes::Scope, signal::*, tasks::TaskRunner};
+
+/// Shortest period of [`use_interval`]
+pub const MIN_PERIOD: Duration = Duration::from_millis(1);
+
+/// Sent to the scope that created a timer when it fires
+struct Elapsed {
+    timer: Scope,
+}
+
+/// Calls f every period while the returned signal is true, setting it to false pauses the
+/// interval and setting it back restarts the period. Stops when the current scope is
+/// disposed. A zero period is treated as [`MIN_PERIOD`]
+pub fn use_interval(period: Duration, f: impl Fn() + 'static) -> Signal<bool> {
+    let period = period.max(MIN_PERIOD);
+    let active = create_signal(true);
+    let timer = spawn_while(active, move |tx| async move {
+        let start = tokio::time::Instant::now() + period;
+        let mut interval = tokio::time::interval_at(start, period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            tx();
+        }
+    });
+    on(move |elapsed: &Elapsed| {
+        if elapsed.timer == timer {
+            f();
+        }
+    });
+    active
+}
+
+/// Calls f once after delay, the returned signal is set to false when it fires. Setting it
+/// to false cancels the timeout and setting it to true starts it again
+pub fn use_timeout(delay: Duration, f: impl Fn() + 'static) -> Signal<bool> {
+    let active = create_signal(true);
+    let timer = spawn_while(active, move |tx| async move {
+        tokio::time::sleep(delay).await;
+        tx();
+    });
+    on(move |elapsed: &Elapsed| {
+        if elapsed.timer == timer {
+            active.set(false);
+            f();
+        }
+    });
+    active
+}
+
+/// Follows signal once it has not changed for delay. Setting the returned active signal to
+/// false pauses the output at its value, setting it back follows signal after the delay
+pub fn debounced<T: Clone + 'static>(
+    signal: impl SignalGet<T> + Clone + 'static,
+    delay: Duration,
+) -> (ReadSignal<T>, Signal<bool>) {
+    let output = create_signal(signal.get_untracked());
+    let active = create_signal(true);
+    let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let timer = create_id();
+    let first = Cell::new(true);
+
+    let signal_c = signal.clone();
+    create_memo(move || {
+        signal_c.with(|_| {});
+        let active = active.get();
+        if first.replace(false) || !active {
+            return;
+        }
+        // Restarted on every change since the rerun cancels the previous delay
+        let tx = tasks.transmitter(owner);
+        tasks.spawn_cancellable(timer, async move {
+            tokio::time::sleep(delay).await;
+            tx.send(Elapsed { timer });
+        });
+        let tasks = tasks.clone();
+        on_cleanup(move || tasks.cancel(timer));
+    });
+
+    let output_c = output.clone();
+    on(move |elapsed: &Elapsed| {
+        if elapsed.timer == timer && active.get_untracked() {
+            output_c.set(signal.get_untracked());
+        }
+    });
+    (ReadSignal(output.0, PhantomData), active)
+}
+
+/// Follows signal at most once per interval, the first change is passed on immediately and
+/// the latest value at the end of the interval. Setting the returned active signal to false
+/// pauses the output at its value, setting it back passes on the value of signal immediately
+pub fn throttled<T: Clone + 'static>(
+    signal: impl SignalGet<T> + Clone + 'static,
+    interval: Duration,
+) -> (ReadSignal<T>, Signal<bool>) {
+    let output = create_signal(signal.get_untracked());
+    let active = create_signal(true);
+    let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let timer = create_id();
+    let open = Rc::new(Cell::new(false));
+    let pending = Rc::new(Cell::new(false));
+
+    let start = {
+        let tasks = tasks.clone();
+        move || {
+            let tx = tasks.transmitter(owner);
+            tasks.spawn_cancellable(timer, async move {
+                tokio::time::sleep(interval).await;
+                tx.send(Elapsed { timer });
+            });
+        }
+    };
+
+    let (signal_c, output_c) = (signal.clone(), output.clone());
+    let (open_c, pending_c, start_c) = (open.clone(), pending.clone(), start.clone());
+    let tasks_c = tasks.clone();
+    let first = Cell::new(true);
+    create_memo(move || {
+        let value = signal_c.get();
+        let active = active.get();
+        if first.replace(false) {
+            return;
+        }
+        if !active {
+            tasks_c.cancel(timer);
+            open_c.set(false);
+            pending_c.set(false);
+            return;
+        }
+        match open_c.replace(true) {
+            true => pending_c.set(true),
+            false => {
+                output_c.set(value);
+                start_c();
+            }
+        }
+    });
+
+    let output_c = output.clone();
+    on(move |elapsed: &Elapsed| {
+        if elapsed.timer != timer || !active.get_untracked() {
+            return;
+        }
+        match pending.replace(false) {
+            true => {
+                output_c.set(signal.get_untracked());
+                start();
+            }
+            false => open.set(false),
+        }
+    });
+    on_cleanup(move || tasks.cancel(timer));
+    (ReadSignal(output.0, PhantomData), active)
+}
+
+/// Runs the future made by f while active is true, f is given a callback sending
+/// [`Elapsed`] for the returned timer
+fn spawn_while<Fu>(active: Signal<bool>, f: impl Fn(Box<dyn Fn() + Send>) -> Fu + 'static) -> Scope
+where
+    Fu: futures::Future<Output = ()> + Send + 'static,
+{
+    let tasks = use_context::<TaskRunner>();
+    let owner = with_runtime(|r| r.get_current_scope());
+    let timer = create_id();
+    create_memo(move || {
+        if !active.get() {
+            return;
+        }
+        let tx = tasks.transmitter(owner);
+        tasks.spawn_cancellable(timer, f(Box::new(move || tx.send(Elapsed { timer }))));
+        let tasks = tasks.clone();
+        on_cleanup(move || tasks.cancel(timer));
+    });
+    timer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ratatui::widget_ref, testing::TestApp};
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_interval_and_timeout() {
+        let (mut app, (interval, timeout)) = TestApp::mount(10, 1, || {
+            let (ticks, fired) = (create_signal(0), create_signal(0));
+            let interval = use_interval(ms(100), move || ticks.update(|t| *t += 1));
+            let timeout = use_timeout(ms(250), move || fired.update(|f| *f += 1));
+            (
+                widget_ref(move || format!("{} {}", ticks.get(), fired.get())),
+                (interval, timeout),
+            )
+        });
+        app.advance(ms(350));
+        assert_eq!(app.to_string(), "3 1\n");
+        assert!(!app.enter(|| timeout.get()));
+
+        app.enter(|| interval.set(false));
+        app.advance(ms(500));
+        assert_eq!(app.to_string(), "3 1\n");
+        app.enter(|| {
+            interval.set(true);
+            timeout.set(true);
+        });
+        app.advance(ms(250));
+        assert_eq!(app.to_string(), "5 2\n");
+    }
+
+    #[test]
+    fn test_zero_interval_clamped() {
+        let (mut app, ticks) = TestApp::mount(10, 1, || {
+            let ticks = create_signal(0);
+            use_interval(Duration::ZERO, move || ticks.update(|t| *t += 1));
+            (widget_ref(move || ticks.get().to_string()), ticks)
+        });
+        app.advance(ms(5));
+        assert_eq!(app.enter(|| ticks.get()), 5);
+    }
+
+    #[test]
+    fn test_throttled_tracks_active_before_changes() {
+        let (mut app, (input, active)) = TestApp::mount(10, 1, || {
+            let input = create_signal(0_i32);
+            let (limited, active) = throttled(input, ms(100));
+            (
+                widget_ref(move || limited.get().to_string()),
+                (input, active),
+            )
+        });
+        app.enter(|| active.set(false));
+        app.enter(|| active.set(true));
+        app.enter(|| input.set(1));
+        app.advance(ms(50));
+        assert_eq!(app.to_string(), "0\n", "Resuming opened an interval");
+        app.advance(ms(50));
+        assert_eq!(app.to_string(), "1\n");
+    }
+
+    #[test]
+    fn test_debounced_and_throttled() {
+        let (mut app, (input, slow_active, limited_active)) = TestApp::mount(10, 1, || {
+            let input = create_signal(0);
+            let (slow, slow_active) = debounced(input, ms(100));
+            let (limited, limited_active) = throttled(input, ms(100));
+            (
+                widget_ref(move || format!("{} {}", slow.get(), limited.get())),
+                (input, slow_active, limited_active),
+            )
+        });
+        let mut type_at = |value, wait| {
+            app.enter(|| input.set(value));
+            app.advance(ms(wait));
+            app.to_string()
+        };
+        assert_eq!(type_at(1, 40), "0 1\n");
+        assert_eq!(type_at(2, 40), "0 1\n");
+        assert_eq!(type_at(3, 40), "0 3\n");
+        assert_eq!(type_at(4, 99), "0 4\n");
+        app.advance(ms(1));
+        assert_eq!(app.to_string(), "4 4\n");
+
+        app.enter(|| {
+            slow_active.set(false);
+            limited_active.set(false);
+            input.set(5);
+        });
+        app.advance(ms(300));
+        assert_eq!(app.to_string(), "4 4\n", "Paused");
+        app.enter(|| {
+            slow_active.set(true);
+            limited_active.set(true);
+        });
+        app.advance(ms(50));
+        assert_eq!(app.to_string(), "4 5\n");
+        app.advance(ms(50));
+        assert_eq!(app.to_string(), "5 5\n");
+    }
+}